@@ -0,0 +1,381 @@
+use std::{
+    fs,
+    path::Path,
+    process::Command,
+    time::{Duration, SystemTime},
+};
+
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, bail},
+};
+use serde::Deserialize;
+
+use crate::back_logic::{project_files::walk, shell::output_with_timeout};
+
+// a wall of warnings drowns the errors, and the prompt budget with them
+const MAX_DIAGNOSTICS: usize = 20;
+const SNIPPET_CONTEXT_LINES: usize = 3;
+// a cold build of a large workspace takes a while, but not forever
+pub const CARGO_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Compiler messages of the crate in the working directory, re-run when a prompt is sent
+/// and the sources changed since the last build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CargoCheck {
+    Check,
+    Clippy,
+}
+
+/// What a build depends on: the newest modification time and the number of the sources
+/// and manifests. The same stamp means the same compiler messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceStamp {
+    newest: SystemTime,
+    files: usize, // catches deleted files, which leave the newest time as it was
+}
+
+impl SourceStamp {
+    /// Stamps the build inputs below the working directory, `None` without any.
+    /// Blocks for a walk of the tree, so it only runs on the dispatcher's threads.
+    pub fn current() -> Option<Self> {
+        let inputs: Vec<String> = walk()
+            .into_iter()
+            .filter(|path| is_build_input(path))
+            .collect();
+        let newest = inputs
+            .iter()
+            .filter_map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+            .max()?;
+        Some(Self {
+            newest,
+            files: inputs.len(),
+        })
+    }
+}
+
+// sources, manifests and cargo configs, `target/` is left out by the .gitignore
+fn is_build_input(path: &str) -> bool {
+    let path = Path::new(path);
+    path.extension()
+        .is_some_and(|ext| ext == "rs" || ext == "toml")
+        || path.file_name().is_some_and(|name| name == "Cargo.lock")
+}
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<Diagnostic>,
+}
+
+#[derive(Deserialize)]
+pub struct Diagnostic {
+    level: String,
+    pub message: String,
+    code: Option<DiagnosticCode>,
+    rendered: Option<String>,
+    spans: Vec<DiagnosticSpan>,
+}
+
+#[derive(Deserialize)]
+struct DiagnosticCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct DiagnosticSpan {
+    file_name: String,
+    line_start: usize,
+    line_end: usize,
+    column_start: usize,
+    column_end: usize,
+    is_primary: bool,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.level == "error"
+    }
+
+    /// `file:line` of the primary span.
+    pub fn location(&self) -> Option<String> {
+        self.primary_span()
+            .map(|span| format!("{}:{}", span.file_name, span.line_start))
+    }
+
+    fn primary_span(&self) -> Option<&DiagnosticSpan> {
+        self.spans.iter().find(|span| span.is_primary)
+    }
+
+    // what makes two messages the same one, messages without a code go by their text
+    fn same_as(&self, other: &Diagnostic) -> bool {
+        let code = |d: &Diagnostic| d.code.as_ref().map(|c| c.code.clone());
+        let span = |d: &Diagnostic| {
+            d.primary_span().map(|s| {
+                (
+                    s.file_name.clone(),
+                    s.line_start,
+                    s.column_start,
+                    s.line_end,
+                    s.column_end,
+                )
+            })
+        };
+        code(self) == code(other)
+            && (self.code.is_some() || self.message == other.message)
+            && span(self) == span(other)
+    }
+
+    // the lines around the primary span, so the model sees more than the one line rustc quotes
+    fn snippet(&self) -> Option<String> {
+        let span = self.primary_span()?;
+        let source = fs::read_to_string(&span.file_name).ok()?;
+        let first = span.line_start.saturating_sub(SNIPPET_CONTEXT_LINES).max(1);
+        let last = span.line_end + SNIPPET_CONTEXT_LINES;
+        let lines: Vec<String> = source
+            .lines()
+            .enumerate()
+            .skip(first - 1)
+            .take(last + 1 - first)
+            .map(|(idx, line)| format!("{:>5} | {}", idx + 1, line))
+            .collect();
+        Some(format!(
+            "{} lines {}-{}:\n{}",
+            span.file_name,
+            first,
+            first + lines.len().saturating_sub(1),
+            lines.join("\n")
+        ))
+    }
+
+    fn to_context(&self) -> String {
+        let rendered = self.rendered.as_deref().unwrap_or(&self.message);
+        match self.snippet() {
+            Some(snippet) => format!("{}\n{}\n", rendered.trim_end(), snippet),
+            None => format!("{}\n", rendered.trim_end()),
+        }
+    }
+}
+
+/// The compiler messages in cargo's `--message-format=json` output, errors first.
+fn parse_diagnostics(stdout: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for line in stdout.lines() {
+        let Ok(msg) = serde_json::from_str::<CargoMessage>(line) else {
+            continue;
+        };
+        let Some(diagnostic) = msg.message.filter(|_| msg.reason == "compiler-message") else {
+            continue;
+        };
+        // lib and test targets report the same message twice
+        if diagnostic.spans.is_empty() || diagnostics.iter().any(|d| d.same_as(&diagnostic)) {
+            continue;
+        }
+        diagnostics.push(diagnostic);
+    }
+    diagnostics.sort_by_key(|d| !d.is_error());
+    diagnostics
+}
+
+impl CargoCheck {
+    pub fn title(self) -> &'static str {
+        match self {
+            CargoCheck::Check => "cargo check",
+            CargoCheck::Clippy => "cargo clippy",
+        }
+    }
+
+    /// Errors first, then warnings, without the "aborting due to" summaries.
+    /// Blocks for the whole build, so it only runs on the dispatcher's threads.
+    pub fn diagnostics(self, timeout: Duration) -> Result<Vec<Diagnostic>> {
+        let subcommand = match self {
+            CargoCheck::Check => "check",
+            CargoCheck::Clippy => "clippy",
+        };
+        let output = output_with_timeout(
+            Command::new("cargo").args([subcommand, "--message-format=json", "--quiet"]),
+            timeout,
+        )
+        .wrap_err("running cargo")?;
+
+        let diagnostics = parse_diagnostics(&String::from_utf8_lossy(&output.stdout));
+        // no compiler messages but a failure means cargo itself failed (no manifest, ...)
+        if diagnostics.is_empty() && !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("{}", stderr.lines().next().unwrap_or("cargo failed"));
+        }
+        Ok(diagnostics)
+    }
+
+    pub fn run(self, timeout: Duration) -> Result<String> {
+        Ok(self.format(&self.diagnostics(timeout)?))
+    }
+
+    /// The context text: every message with the source around it, errors first.
+    pub fn format(self, diagnostics: &[Diagnostic]) -> String {
+        if diagnostics.is_empty() {
+            return format!("{}: no errors or warnings\n", self.title());
+        }
+        let mut out: Vec<String> = diagnostics
+            .iter()
+            .take(MAX_DIAGNOSTICS)
+            .map(Diagnostic::to_context)
+            .collect();
+        if diagnostics.len() > MAX_DIAGNOSTICS {
+            out.push(format!(
+                "... {} more not shown\n",
+                diagnostics.len() - MAX_DIAGNOSTICS
+            ));
+        }
+        out.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    // one line of `cargo check --message-format=json`
+    fn compiler_message(level: &str, code: Option<&str>, line: usize, text: &str) -> String {
+        json!({
+            "reason": "compiler-message",
+            "package_id": "nask 0.1.0",
+            "message": {
+                "level": level,
+                "message": text,
+                "code": code.map(|code| json!({ "code": code, "explanation": null })),
+                "rendered": format!("{}: {}\n --> src/missing.rs:{}:5\n", level, text, line),
+                "spans": [{
+                    "file_name": "src/missing.rs",
+                    "line_start": line,
+                    "line_end": line,
+                    "column_start": 5,
+                    "column_end": 9,
+                    "is_primary": true,
+                }],
+                "children": [],
+            },
+        })
+        .to_string()
+    }
+
+    fn messages(diagnostics: &[Diagnostic]) -> Vec<&str> {
+        diagnostics.iter().map(|d| d.message.as_str()).collect()
+    }
+
+    #[test]
+    fn only_compiler_messages_with_spans_are_parsed() {
+        let stdout = [
+            json!({ "reason": "compiler-artifact", "package_id": "libc 0.2.0" }).to_string(),
+            compiler_message(
+                "warning",
+                Some("unused_variables"),
+                3,
+                "unused variable: `x`",
+            ),
+            json!({
+                "reason": "compiler-message",
+                "message": {
+                    "level": "error",
+                    "message": "aborting due to 1 previous error",
+                    "code": null,
+                    "rendered": "error: aborting due to 1 previous error\n",
+                    "spans": [],
+                },
+            })
+            .to_string(),
+            String::from("Compiling nask v0.1.0"),
+            json!({ "reason": "build-finished", "success": false }).to_string(),
+        ]
+        .join("\n");
+        let diagnostics = parse_diagnostics(&stdout);
+        assert_eq!(messages(&diagnostics), ["unused variable: `x`"]);
+        assert!(!diagnostics[0].is_error());
+        assert_eq!(
+            diagnostics[0].location().as_deref(),
+            Some("src/missing.rs:3")
+        );
+    }
+
+    #[test]
+    fn repeats_are_dropped_by_code_and_span() {
+        let stdout = [
+            compiler_message(
+                "warning",
+                Some("dead_code"),
+                3,
+                "function `a` is never used",
+            ),
+            // the test target reports it again
+            compiler_message(
+                "warning",
+                Some("dead_code"),
+                3,
+                "function `a` is never used",
+            ),
+            compiler_message(
+                "warning",
+                Some("dead_code"),
+                7,
+                "function `b` is never used",
+            ),
+            // without a code the text tells them apart
+            compiler_message("warning", None, 9, "first"),
+            compiler_message("warning", None, 9, "second"),
+            compiler_message("warning", None, 9, "first"),
+        ]
+        .join("\n");
+        assert_eq!(
+            messages(&parse_diagnostics(&stdout)),
+            [
+                "function `a` is never used",
+                "function `b` is never used",
+                "first",
+                "second",
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_come_before_warnings() {
+        let stdout = [
+            compiler_message("warning", Some("unused_mut"), 1, "warning one"),
+            compiler_message("error", Some("E0308"), 2, "mismatched types"),
+            compiler_message("warning", Some("unused_mut"), 3, "warning two"),
+            compiler_message("error", Some("E0425"), 4, "cannot find value"),
+        ]
+        .join("\n");
+        assert_eq!(
+            messages(&parse_diagnostics(&stdout)),
+            [
+                "mismatched types",
+                "cannot find value",
+                "warning one",
+                "warning two",
+            ]
+        );
+    }
+
+    #[test]
+    fn format_stops_at_the_limit() {
+        let stdout = (1..=MAX_DIAGNOSTICS + 5)
+            .map(|line| compiler_message("warning", Some("dead_code"), line, "never used"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let diagnostics = parse_diagnostics(&stdout);
+        assert_eq!(diagnostics.len(), MAX_DIAGNOSTICS + 5);
+
+        let context = CargoCheck::Check.format(&diagnostics);
+        assert_eq!(
+            context.matches("warning: never used").count(),
+            MAX_DIAGNOSTICS
+        );
+        assert!(context.ends_with("... 5 more not shown\n"));
+        assert_eq!(
+            CargoCheck::Clippy.format(&[]),
+            "cargo clippy: no errors or warnings\n"
+        );
+    }
+}
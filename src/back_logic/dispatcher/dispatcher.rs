@@ -1,17 +1,45 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, thread, time::Duration};
 
-use crate::{back_logic::message_loop::Command, ui::app_ui_state::UiSink};
+use crate::{
+    back_logic::{
+        cargo::{CARGO_TIMEOUT, SourceStamp},
+        git::GIT_TIMEOUT,
+        message_loop::Command,
+        project_files::walk,
+        shell::{SHELL_TIMEOUT, run_shell},
+        tmux::{TMUX_TIMEOUT, capture_pane, list_panes},
+    },
+    ui::app_ui_state::{ContextSource, TokenUsage, UiSink},
+};
+
+// how long a prompt waits for its git, cargo and tmux contexts before the last output is sent
+const REFRESH_TIMEOUT: Duration = Duration::from_secs(20);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CommandKind {
     ChatMessage,
+    RunShell,
+    RunGit,
+    RunCargo,
+    ListTmuxPanes,
+    CaptureTmux,
+    WalkProjectFiles,
+    RefreshContexts,
+    #[allow(dead_code)] // no Command maps to it yet
     Quit,
 }
 
 impl Command {
     pub fn kind(&self) -> CommandKind {
         match self {
-            Command::ChatMessage(_) => CommandKind::ChatMessage,
+            Command::ChatMessage { .. } => CommandKind::ChatMessage,
+            Command::RunShell { .. } => CommandKind::RunShell,
+            Command::RunGit { .. } => CommandKind::RunGit,
+            Command::RunCargo { .. } => CommandKind::RunCargo,
+            Command::ListTmuxPanes { .. } => CommandKind::ListTmuxPanes,
+            Command::CaptureTmux { .. } => CommandKind::CaptureTmux,
+            Command::WalkProjectFiles => CommandKind::WalkProjectFiles,
+            Command::RefreshContexts { .. } => CommandKind::RefreshContexts,
         }
     }
 }
@@ -28,6 +56,19 @@ impl Dispatcher {
     pub fn new() -> Self {
         let mut dispatches: HashMap<CommandKind, Box<dyn Dispatch>> = HashMap::new();
         dispatches.insert(CommandKind::ChatMessage, Box::new(ChatMessageDispatch));
+        dispatches.insert(CommandKind::RunShell, Box::new(RunShellDispatch));
+        dispatches.insert(CommandKind::RunGit, Box::new(RunGitDispatch));
+        dispatches.insert(CommandKind::RunCargo, Box::new(RunCargoDispatch));
+        dispatches.insert(CommandKind::ListTmuxPanes, Box::new(ListTmuxPanesDispatch));
+        dispatches.insert(CommandKind::CaptureTmux, Box::new(CaptureTmuxDispatch));
+        dispatches.insert(
+            CommandKind::WalkProjectFiles,
+            Box::new(WalkProjectFilesDispatch),
+        );
+        dispatches.insert(
+            CommandKind::RefreshContexts,
+            Box::new(RefreshContextsDispatch),
+        );
         Self { dispatches }
     }
 
@@ -41,7 +82,173 @@ impl Dispatcher {
 struct ChatMessageDispatch;
 impl Dispatch for ChatMessageDispatch {
     fn execute(&self, cmd: &Command, ui_sink: &UiSink) {
-        let Command::ChatMessage(msg) = cmd;
-        ui_sink.chat_answer(String::from(msg), true);
+        let Command::ChatMessage {
+            prompt,
+            contexts,
+            model,
+            system,
+        } = cmd
+        else {
+            return;
+        };
+        ui_sink.generation_started();
+
+        let mut answer = format!("[{}] {}", model, prompt);
+        let mut prompt_chars = prompt.chars().count();
+        if let Some(system) = system {
+            prompt_chars += system.chars().count();
+        }
+        for context in contexts {
+            let chars = context.content.chars().count();
+            prompt_chars += chars;
+            answer.push_str(&format!("\n[{}: {} chars attached]", context.label, chars));
+        }
+        let usage = TokenUsage {
+            prompt_tokens: prompt_chars.div_ceil(4),
+            completion_tokens: answer.chars().count().div_ceil(4),
+        };
+        ui_sink.chat_answer(answer, true);
+        ui_sink.generation_finished(usage);
+    }
+}
+
+struct RunShellDispatch;
+impl Dispatch for RunShellDispatch {
+    fn execute(&self, cmd: &Command, ui_sink: &UiSink) {
+        let Command::RunShell { command } = cmd else {
+            return;
+        };
+        // a slow command must not hold up chat requests, and quitting does not wait for it
+        let command = command.clone();
+        let ui_sink = ui_sink.clone();
+        thread::spawn(move || ui_sink.shell_finished(run_shell(&command, SHELL_TIMEOUT)));
+    }
+}
+
+struct RunGitDispatch;
+impl Dispatch for RunGitDispatch {
+    fn execute(&self, cmd: &Command, ui_sink: &UiSink) {
+        let Command::RunGit { git, review } = cmd else {
+            return;
+        };
+        let (git, review) = (git.clone(), *review);
+        let ui_sink = ui_sink.clone();
+        thread::spawn(move || {
+            let output = git.run(GIT_TIMEOUT).map_err(|e| format!("{:#}", e));
+            ui_sink.git_finished(git, review, output);
+        });
+    }
+}
+
+struct RunCargoDispatch;
+impl Dispatch for RunCargoDispatch {
+    fn execute(&self, cmd: &Command, ui_sink: &UiSink) {
+        let Command::RunCargo { cargo, fix } = *cmd else {
+            return;
+        };
+        let ui_sink = ui_sink.clone();
+        thread::spawn(move || {
+            // stamped before the build, an edit while it runs makes the next prompt re-run it
+            let built = SourceStamp::current();
+            let diagnostics = cargo
+                .diagnostics(CARGO_TIMEOUT)
+                .map_err(|e| format!("{:#}", e));
+            ui_sink.cargo_finished(cargo, fix, diagnostics, built);
+        });
+    }
+}
+
+struct ListTmuxPanesDispatch;
+impl Dispatch for ListTmuxPanesDispatch {
+    fn execute(&self, cmd: &Command, ui_sink: &UiSink) {
+        let Command::ListTmuxPanes { socket } = cmd else {
+            return;
+        };
+        let socket = socket.clone();
+        let ui_sink = ui_sink.clone();
+        thread::spawn(move || {
+            let panes = list_panes(socket.as_deref(), TMUX_TIMEOUT).map_err(|e| format!("{:#}", e));
+            ui_sink.tmux_panes_listed(socket, panes);
+        });
+    }
+}
+
+struct CaptureTmuxDispatch;
+impl Dispatch for CaptureTmuxDispatch {
+    fn execute(&self, cmd: &Command, ui_sink: &UiSink) {
+        let Command::CaptureTmux {
+            socket,
+            pane,
+            lines,
+        } = cmd
+        else {
+            return;
+        };
+        let (socket, pane, lines) = (socket.clone(), pane.clone(), *lines);
+        let ui_sink = ui_sink.clone();
+        thread::spawn(move || {
+            let output = capture_pane(socket.as_deref(), &pane, lines, TMUX_TIMEOUT)
+                .map_err(|e| format!("{:#}", e));
+            ui_sink.tmux_captured(output);
+        });
+    }
+}
+
+struct WalkProjectFilesDispatch;
+impl Dispatch for WalkProjectFilesDispatch {
+    fn execute(&self, _cmd: &Command, ui_sink: &UiSink) {
+        let ui_sink = ui_sink.clone();
+        thread::spawn(move || ui_sink.project_files_walked(walk().into()));
+    }
+}
+
+struct RefreshContextsDispatch;
+impl Dispatch for RefreshContextsDispatch {
+    fn execute(&self, cmd: &Command, ui_sink: &UiSink) {
+        let Command::RefreshContexts {
+            sources,
+            cargo_built,
+        } = cmd
+        else {
+            return;
+        };
+        let (sources, cargo_built) = (sources.clone(), cargo_built.clone());
+        let ui_sink = ui_sink.clone();
+        thread::spawn(move || {
+            // a build of unchanged sources reports the same, the last output still holds
+            let stamp = sources
+                .iter()
+                .any(|source| matches!(source, ContextSource::Cargo(_)))
+                .then(SourceStamp::current)
+                .flatten();
+            let sources: Vec<ContextSource> = sources
+                .into_iter()
+                .filter(|source| match source {
+                    ContextSource::Cargo(cargo) => {
+                        !stamp.is_some_and(|stamp| cargo_built.contains(&(*cargo, stamp)))
+                    }
+                    _ => true,
+                })
+                .collect();
+            // side by side, so the prompt waits for the slowest one and not for their sum
+            let results = thread::scope(|scope| {
+                let runs: Vec<_> = sources
+                    .iter()
+                    .map(|source| scope.spawn(|| source.refresh(REFRESH_TIMEOUT)))
+                    .collect();
+                sources
+                    .iter()
+                    .zip(runs)
+                    .filter_map(|(source, run)| {
+                        let result = match run.join().ok()?? {
+                            Ok(content) => Ok(content),
+                            Err(e) => Err(format!("{:#}", e)),
+                        };
+                        Some((source.clone(), result))
+                    })
+                    .collect()
+            });
+            ui_sink.contexts_refreshed(results, stamp);
+        });
     }
 }
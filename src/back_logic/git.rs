@@ -0,0 +1,58 @@
+use std::{process::Command, time::Duration};
+
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, bail},
+};
+
+use crate::back_logic::shell::output_with_timeout;
+
+// attaching waits for git, a hanging repository must not freeze the UI for good
+pub const GIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A context computed by the git CLI, it is re-run every time a prompt is sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitContext {
+    Unstaged,
+    Staged,
+    Branch(String),                     // everything since the branch point
+    Log { path: String, count: usize }, // `git log -p` of one path
+}
+
+impl GitContext {
+    fn args(&self) -> Vec<String> {
+        match self {
+            GitContext::Unstaged => vec![String::from("diff")],
+            GitContext::Staged => vec![String::from("diff"), String::from("--cached")],
+            // a branch starting with `-` must not be taken for an option
+            GitContext::Branch(branch) => vec![
+                String::from("diff"),
+                String::from("--end-of-options"),
+                format!("{}...HEAD", branch),
+            ],
+            GitContext::Log { path, count } => vec![
+                String::from("log"),
+                String::from("-p"),
+                format!("-n{}", count),
+                String::from("--"),
+                path.clone(),
+            ],
+        }
+    }
+
+    /// The command line, shown as the entry name in the context panel.
+    pub fn title(&self) -> String {
+        format!("git {}", self.args().join(" "))
+    }
+
+    pub fn run(&self, timeout: Duration) -> Result<String> {
+        let output = output_with_timeout(Command::new("git").args(self.args()), timeout)
+            .wrap_err("running git")?;
+        if !output.status.success() {
+            // the first line says what went wrong, the rest are hints
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("{}", stderr.lines().next().unwrap_or("git failed"));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
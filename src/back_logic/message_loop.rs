@@ -7,10 +7,51 @@ use std::{
     thread::JoinHandle,
 };
 
-use crate::{back_logic::dispatcher::dispatcher::Dispatcher, ui::app_ui_state::UiSink};
+use crate::{
+    back_logic::{
+        cargo::{CargoCheck, SourceStamp},
+        dispatcher::dispatcher::Dispatcher,
+        git::GitContext,
+    },
+    ui::app_ui_state::{ContextSource, UiSink},
+};
+
+pub struct ContextAttachment {
+    pub label: String,
+    pub content: String,
+}
 
 pub enum Command {
-    ChatMessage(String),
+    ChatMessage {
+        prompt: String,
+        contexts: Vec<ContextAttachment>,
+        model: String,
+        system: Option<String>,
+    },
+    RunShell {
+        command: String,
+    },
+    RunGit {
+        git: GitContext,
+        review: bool, // ask for a review once the diff is attached
+    },
+    RunCargo {
+        cargo: CargoCheck,
+        fix: bool, // send the first error to the model once the build is done
+    },
+    ListTmuxPanes {
+        socket: Option<String>,
+    },
+    CaptureTmux {
+        socket: Option<String>,
+        pane: String, // id or position
+        lines: usize,
+    },
+    WalkProjectFiles,
+    RefreshContexts {
+        sources: Vec<ContextSource>,
+        cargo_built: Vec<(CargoCheck, SourceStamp)>, // cargo is skipped for unchanged sources
+    },
 }
 
 pub struct ThreadSafeDeque<Command> {
@@ -1,2 +1,7 @@
+pub mod cargo;
 pub mod dispatcher;
+pub mod git;
 pub mod message_loop;
+pub mod project_files;
+pub mod shell;
+pub mod tmux;
@@ -0,0 +1,77 @@
+use std::{
+    fs::{self, File},
+    io::Read,
+    time::Duration,
+};
+
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, bail, eyre},
+};
+use ignore::WalkBuilder;
+
+// enough for any sane project, keeps the first keystroke fast in huge trees
+const MAX_PROJECT_FILES: usize = 20_000;
+// long enough for a burst of keystrokes, short enough that new files show up
+pub const CACHE_TTL: Duration = Duration::from_secs(5);
+// larger files would eat the prompt budget, and reading them stalls the UI
+pub const MAX_ATTACH_BYTES: u64 = 256 * 1024;
+// the preview only needs the top of a file, a huge log must not stall the cursor
+const PREVIEW_BYTES: u64 = 16 * 1024;
+
+/// Files below the working directory as sorted relative paths, honoring `.gitignore`
+/// and `.naskignore` (same syntax). Blocks for the whole walk, run it off the UI thread.
+pub fn walk() -> Vec<String> {
+    // walked in name order, so a tree over the limit always keeps the same files
+    let mut files: Vec<String> = WalkBuilder::new(".")
+        .add_custom_ignore_filename(".naskignore")
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .filter_map(|entry| {
+            let path = entry.path().strip_prefix(".").ok()?;
+            Some(path.to_string_lossy().into_owned())
+        })
+        .take(MAX_PROJECT_FILES)
+        .collect();
+    files.sort();
+    files
+}
+
+/// Reads a file to attach it as context, refuses anything over `MAX_ATTACH_BYTES`
+/// and anything that is no regular text file.
+pub fn read_attachable(path: &str) -> Result<String> {
+    let metadata = fs::metadata(path).wrap_err_with(|| format!("reading {}", path))?;
+    if !metadata.is_file() {
+        bail!("{} is not a regular file", path);
+    }
+    if metadata.len() > MAX_ATTACH_BYTES {
+        bail!(
+            "{} is {} KiB, over the {} KiB limit",
+            path,
+            metadata.len() / 1024,
+            MAX_ATTACH_BYTES / 1024
+        );
+    }
+    let bytes = fs::read(path).wrap_err_with(|| format!("reading {}", path))?;
+    String::from_utf8(bytes).map_err(|_| eyre!("{} is not a text file", path))
+}
+
+// the first `PREVIEW_BYTES` of a text file, a char cut at the end is dropped
+pub fn read_head(path: &str) -> Option<String> {
+    let mut head = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(PREVIEW_BYTES).read_to_end(&mut head))
+        .ok()?;
+    match String::from_utf8(head) {
+        Ok(text) => Some(text),
+        Err(e) if e.utf8_error().error_len().is_none() => {
+            let valid = e.utf8_error().valid_up_to();
+            let mut head = e.into_bytes();
+            head.truncate(valid);
+            String::from_utf8(head).ok()
+        }
+        Err(_) => None,
+    }
+}
@@ -0,0 +1,362 @@
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    sync::{Arc, Mutex, mpsc},
+    thread,
+    time::{Duration, Instant},
+};
+
+pub const SHELL_TIMEOUT: Duration = Duration::from_secs(60);
+// what ends up in the prompt, the first bytes and the last bytes are kept
+const MAX_OUTPUT_BYTES: usize = 16 * 1024;
+const KEEP_HEAD_BYTES: usize = 2 * 1024;
+const KEEP_TAIL_BYTES: usize = MAX_OUTPUT_BYTES - KEEP_HEAD_BYTES;
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+// a background job may keep the pipe open after the command exited
+const DRAIN_GRACE: Duration = Duration::from_millis(200);
+
+pub enum ShellExit {
+    Status(ExitStatus),
+    TimedOut(Duration),
+    FailedToStart, // the reason is in the output
+}
+
+pub struct ShellOutput {
+    pub command: String,
+    pub exit: ShellExit,
+    pub output: String, // stdout and stderr interleaved, already truncated
+}
+
+impl ShellOutput {
+    /// `$ cargo test (exit 101)`, the name of the context entry.
+    pub fn title(&self) -> String {
+        let exit = match &self.exit {
+            ShellExit::Status(status) => match status.code() {
+                Some(code) => format!("exit {}", code),
+                None => String::from("killed"),
+            },
+            ShellExit::TimedOut(after) => format!("timed out after {}s", after.as_secs()),
+            ShellExit::FailedToStart => String::from("failed to start"),
+        };
+        format!("$ {} ({})", self.command, exit)
+    }
+}
+
+// only the head and the last bytes are held, everything in between is counted
+#[derive(Default)]
+struct Captured {
+    head: Vec<u8>,
+    tail: VecDeque<u8>,
+    total: usize,
+}
+
+impl Captured {
+    fn push(&mut self, mut bytes: &[u8]) {
+        self.total += bytes.len();
+        let room = KEEP_HEAD_BYTES
+            .saturating_sub(self.head.len())
+            .min(bytes.len());
+        self.head.extend_from_slice(&bytes[..room]);
+        bytes = &bytes[room..];
+        if bytes.len() >= KEEP_TAIL_BYTES {
+            self.tail.clear();
+            bytes = &bytes[bytes.len() - KEEP_TAIL_BYTES..];
+        }
+        let overflow = (self.tail.len() + bytes.len()).saturating_sub(KEEP_TAIL_BYTES);
+        self.tail.drain(..overflow);
+        self.tail.extend(bytes);
+    }
+
+    // cut at char boundaries, a multi-byte char split by the ring is dropped
+    fn into_output(self) -> String {
+        let tail: Vec<u8> = self.tail.into();
+        let kept = self.head.len() + tail.len();
+        if kept == self.total {
+            let mut raw = self.head;
+            raw.extend_from_slice(&tail);
+            return String::from_utf8_lossy(&raw).into_owned();
+        }
+        let head_end = match std::str::from_utf8(&self.head) {
+            Ok(_) => self.head.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        let tail_start = tail
+            .iter()
+            .position(|b| b & 0b1100_0000 != 0b1000_0000)
+            .unwrap_or(tail.len());
+        format!(
+            "{}\n[... {} bytes cut ...]\n{}",
+            String::from_utf8_lossy(&self.head[..head_end]),
+            self.total - head_end - (tail.len() - tail_start),
+            String::from_utf8_lossy(&tail[tail_start..])
+        )
+    }
+}
+
+// in a process group of its own, killing the group also takes down what the command spawned
+#[cfg(unix)]
+fn own_process_group(command: &mut Command) -> &mut Command {
+    command.process_group(0)
+}
+
+#[cfg(not(unix))]
+fn own_process_group(command: &mut Command) -> &mut Command {
+    command
+}
+
+#[cfg(unix)]
+fn kill_group(child: &mut Child) {
+    let Ok(pgid) = libc::pid_t::try_from(child.id()) else {
+        let _ = child.kill();
+        return;
+    };
+    // SAFETY: killpg only sends a signal, the group is the child's own (see own_process_group)
+    // and still exists, the child is not reaped before this returns
+    if unsafe { libc::killpg(pgid, libc::SIGKILL) } != 0 {
+        let _ = child.kill();
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// Runs `command` with `sh -c` in the working directory. Only ever called for a command the
+/// user typed (`!cmd`, `/run cmd`); it and everything it started are killed after `timeout`.
+/// Blocks for as long as the command runs, callers run it on a thread of its own.
+pub fn run_shell(command: &str, timeout: Duration) -> ShellOutput {
+    let failed = |e: io::Error| ShellOutput {
+        command: command.to_string(),
+        exit: ShellExit::FailedToStart,
+        output: e.to_string(),
+    };
+
+    // one pipe for both streams keeps them in the order they were written
+    let (mut reader, writer) = match io::pipe() {
+        Ok(pipe) => pipe,
+        Err(e) => return failed(e),
+    };
+    let mut child = match writer.try_clone().and_then(|stderr| {
+        own_process_group(
+            Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .stdin(Stdio::null())
+                .stdout(writer)
+                .stderr(stderr),
+        )
+        .spawn()
+    }) {
+        Ok(child) => child,
+        Err(e) => return failed(e),
+    };
+
+    let captured = Arc::new(Mutex::new(Captured::default()));
+    let (done_tx, done_rx) = mpsc::channel();
+    {
+        let captured = Arc::clone(&captured);
+        thread::spawn(move || {
+            let mut chunk = [0u8; 8192];
+            while let Ok(n) = reader.read(&mut chunk) {
+                if n == 0 {
+                    break;
+                }
+                captured
+                    .lock()
+                    .expect("shell capture mutex poisoned")
+                    .push(&chunk[..n]);
+            }
+            let _ = done_tx.send(());
+        });
+    }
+
+    let started = Instant::now();
+    let exit = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break ShellExit::Status(status),
+            Ok(None) if started.elapsed() >= timeout => {
+                kill_group(&mut child);
+                let _ = child.wait();
+                break ShellExit::TimedOut(timeout);
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(_) => break ShellExit::FailedToStart,
+        }
+    };
+    let _ = done_rx.recv_timeout(DRAIN_GRACE);
+
+    let captured = std::mem::take(&mut *captured.lock().expect("shell capture mutex poisoned"));
+    ShellOutput {
+        command: command.to_string(),
+        exit,
+        output: captured.into_output(),
+    }
+}
+
+// collects a pipe on a thread of its own, `done` hears when the pipe is closed
+fn drain(pipe: Option<impl Read + Send + 'static>, done: mpsc::Sender<()>) -> Arc<Mutex<Vec<u8>>> {
+    let bytes = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&bytes);
+    thread::spawn(move || {
+        if let Some(mut pipe) = pipe {
+            let mut chunk = [0u8; 8192];
+            while let Ok(n) = pipe.read(&mut chunk) {
+                if n == 0 {
+                    break;
+                }
+                sink.lock()
+                    .expect("pipe drain mutex poisoned")
+                    .extend_from_slice(&chunk[..n]);
+            }
+        }
+        let _ = done.send(());
+    });
+    bytes
+}
+
+/// `Command::output` that gives up after `timeout`, the command and what it started are killed.
+pub fn output_with_timeout(command: &mut Command, timeout: Duration) -> io::Result<Output> {
+    let mut child = own_process_group(
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )
+    .spawn()?;
+    // drained while waiting, a full pipe would stall the command
+    let (done_tx, done_rx) = mpsc::channel();
+    let stdout = drain(child.stdout.take(), done_tx.clone());
+    let stderr = drain(child.stderr.take(), done_tx);
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            kill_group(&mut child);
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("timed out after {}s", timeout.as_secs()),
+            ));
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    let grace_end = Instant::now() + DRAIN_GRACE;
+    for _ in 0..2 {
+        let left = grace_end.saturating_duration_since(Instant::now());
+        if done_rx.recv_timeout(left).is_err() {
+            break;
+        }
+    }
+    let take = |bytes: &Mutex<Vec<u8>>| {
+        std::mem::take(&mut *bytes.lock().expect("pipe drain mutex poisoned"))
+    };
+    Ok(Output {
+        status,
+        stdout: take(&stdout),
+        stderr: take(&stderr),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captured(chunks: &[&[u8]]) -> String {
+        let mut captured = Captured::default();
+        for chunk in chunks {
+            captured.push(chunk);
+        }
+        captured.into_output()
+    }
+
+    #[test]
+    fn short_output_is_kept_whole() {
+        assert_eq!(captured(&[b"hello ", b"world"]), "hello world");
+    }
+
+    #[test]
+    fn long_output_keeps_head_and_tail() {
+        let head = vec![b'h'; KEEP_HEAD_BYTES];
+        let middle = vec![b'm'; 100_000];
+        let tail = vec![b't'; KEEP_TAIL_BYTES];
+        let output = captured(&[&head, &middle[..500], &middle[500..], &tail]);
+
+        let (kept_head, rest) = output.split_once('\n').unwrap();
+        let (marker, kept_tail) = rest.split_once('\n').unwrap();
+        assert_eq!(kept_head.len(), KEEP_HEAD_BYTES);
+        assert!(kept_head.bytes().all(|b| b == b'h'));
+        assert_eq!(marker, "[... 100000 bytes cut ...]");
+        assert_eq!(kept_tail.len(), KEEP_TAIL_BYTES);
+        assert!(kept_tail.bytes().all(|b| b == b't'));
+    }
+
+    #[test]
+    fn cut_lands_on_char_boundaries() {
+        // `é` is two bytes, one of them is split off at each end
+        let mut head = vec![b'a'; KEEP_HEAD_BYTES - 1];
+        head.extend("é".as_bytes());
+        let mut tail = "é".as_bytes().to_vec();
+        tail.extend(vec![b'z'; KEEP_TAIL_BYTES - 1]);
+        let output = captured(&[&head, &[b'm'; 10], &tail]);
+
+        let (kept_head, rest) = output.split_once('\n').unwrap();
+        let (marker, kept_tail) = rest.split_once('\n').unwrap();
+        assert_eq!(kept_head.len(), KEEP_HEAD_BYTES - 1);
+        assert_eq!(marker, "[... 14 bytes cut ...]");
+        assert_eq!(kept_tail.len(), KEEP_TAIL_BYTES - 1);
+    }
+
+    #[test]
+    fn title_names_the_exit() {
+        let output = ShellOutput {
+            command: String::from("sleep 99"),
+            exit: ShellExit::TimedOut(Duration::from_secs(60)),
+            output: String::new(),
+        };
+        assert_eq!(output.title(), "$ sleep 99 (timed out after 60s)");
+
+        let output = ShellOutput {
+            exit: ShellExit::FailedToStart,
+            ..output
+        };
+        assert_eq!(output.title(), "$ sleep 99 (failed to start)");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_shell_reports_the_exit_code() {
+        let output = run_shell("echo out; echo err >&2; exit 3", SHELL_TIMEOUT);
+        assert_eq!(output.title(), "$ echo out; echo err >&2; exit 3 (exit 3)");
+        assert_eq!(output.output, "out\nerr\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_shell_kills_on_timeout() {
+        let started = Instant::now();
+        let output = run_shell("sleep 30", Duration::from_millis(200));
+        assert!(matches!(output.exit, ShellExit::TimedOut(_)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn background_job_does_not_hold_the_output() {
+        let started = Instant::now();
+        let output = output_with_timeout(
+            Command::new("sh").args(["-c", "sleep 30 & echo hi"]),
+            SHELL_TIMEOUT,
+        )
+        .unwrap();
+        assert_eq!(output.stdout, b"hi\n");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,200 @@
+use std::{env, process::Command, time::Duration};
+
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, bail},
+};
+
+use crate::back_logic::shell::output_with_timeout;
+
+pub const DEFAULT_SCROLLBACK_LINES: usize = 200;
+// a wedged tmux server must not freeze the UI
+pub const TMUX_TIMEOUT: Duration = Duration::from_secs(5);
+
+// `socket` is a `-L` server name, without one the server nask runs in
+fn tmux(socket: Option<&str>, args: &[&str], timeout: Duration) -> Result<String> {
+    let mut command = Command::new("tmux");
+    match socket {
+        Some(socket) => {
+            command.args(["-L", socket]);
+        }
+        None if env::var_os("TMUX").is_none() => bail!("not running inside tmux"),
+        None => {}
+    }
+    let output = output_with_timeout(command.args(args), timeout).wrap_err("running tmux")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{}", stderr.lines().next().unwrap_or("tmux failed"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TmuxPane {
+    pub id: String,       // `%3`, stable while the pane lives
+    pub position: String, // `window.pane`, what the status bar shows
+    pub command: String,
+    pub title: String,
+}
+
+const LIST_PANES_FORMAT: &str =
+    "#{pane_id}\t#{window_index}.#{pane_index}\t#{pane_current_command}\t#{pane_title}";
+
+// one pane per `LIST_PANES_FORMAT` line, the title may contain tabs itself
+fn parse_panes(listing: &str, own: Option<&str>) -> Vec<TmuxPane> {
+    listing
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\t');
+            Some(TmuxPane {
+                id: fields.next()?.to_string(),
+                position: fields.next()?.to_string(),
+                command: fields.next()?.to_string(),
+                title: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .filter(|pane| own != Some(pane.id.as_str()))
+        .collect()
+}
+
+/// The panes of the current session, nask's own pane is left out.
+pub fn list_panes(socket: Option<&str>, timeout: Duration) -> Result<Vec<TmuxPane>> {
+    let listing = tmux(
+        socket,
+        &["list-panes", "-s", "-F", LIST_PANES_FORMAT],
+        timeout,
+    )?;
+    // TMUX_PANE names a pane of the server nask runs in
+    let own = env::var("TMUX_PANE").ok().filter(|_| socket.is_none());
+    Ok(parse_panes(&listing, own.as_deref()))
+}
+
+/// The last lines of a pane, captured again every time a prompt is sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TmuxCapture {
+    pub socket: Option<String>,
+    pub pane_id: String,
+    pub lines: usize,
+}
+
+impl TmuxCapture {
+    pub fn run(&self, timeout: Duration) -> Result<String> {
+        let start = format!("-{}", self.lines);
+        // -J joins wrapped lines, so a long stack trace line stays one line
+        let captured = tmux(
+            self.socket.as_deref(),
+            &[
+                "capture-pane",
+                "-p",
+                "-J",
+                "-t",
+                &self.pane_id,
+                "-S",
+                &start,
+            ],
+            timeout,
+        )?;
+        Ok(format!("{}\n", captured.trim_end()))
+    }
+}
+
+/// Finds `pane` by id (`%3`) or by position (`1.0`) and captures its last `lines`.
+pub fn capture_pane(
+    socket: Option<&str>,
+    pane: &str,
+    lines: usize,
+    timeout: Duration,
+) -> Result<(TmuxPane, TmuxCapture, String)> {
+    let Some(pane) = list_panes(socket, timeout)?
+        .into_iter()
+        .find(|p| p.id == pane || p.position == pane)
+    else {
+        bail!("no pane `{}` in this session", pane);
+    };
+    let capture = TmuxCapture {
+        socket: socket.map(str::to_string),
+        pane_id: pane.id.clone(),
+        lines,
+    };
+    let content = capture.run(timeout)?;
+    Ok((pane, capture, content))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{process, thread, time::Instant};
+
+    use super::*;
+
+    #[test]
+    fn parse_panes_skips_own_and_broken_lines() {
+        let listing = "%1\t0.0\tzsh\thost\n\
+                       %2\t0.1\tvim\tmain.rs\tsplit\n\
+                       %3\t1.0\tcargo\n\
+                       garbage\n";
+        let panes = parse_panes(listing, Some("%1"));
+        assert_eq!(
+            panes,
+            [
+                TmuxPane {
+                    id: String::from("%2"),
+                    position: String::from("0.1"),
+                    command: String::from("vim"),
+                    title: String::from("main.rs\tsplit"),
+                },
+                TmuxPane {
+                    id: String::from("%3"),
+                    position: String::from("1.0"),
+                    command: String::from("cargo"),
+                    title: String::new(),
+                },
+            ]
+        );
+    }
+
+    // a server of its own, so the test neither needs nor touches the user's tmux
+    struct PrivateServer(String);
+
+    impl Drop for PrivateServer {
+        fn drop(&mut self) {
+            let _ = Command::new("tmux")
+                .args(["-L", &self.0, "kill-server"])
+                .output();
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn captures_a_pane_of_a_private_server() {
+        if Command::new("tmux").arg("-V").output().is_err() {
+            return; // tmux is not installed
+        }
+        let server = PrivateServer(format!("nask-test-{}", process::id()));
+        let started = Command::new("tmux")
+            .args(["-L", &server.0, "-f", "/dev/null", "new-session", "-d"])
+            .args(["-x", "80", "-y", "24"])
+            .arg("printf 'hello from the pane\\n'; sleep 30")
+            .status()
+            .unwrap();
+        assert!(started.success());
+
+        let socket = Some(server.0.as_str());
+        let panes = list_panes(socket, TMUX_TIMEOUT).unwrap();
+        assert_eq!(panes.len(), 1);
+        assert_eq!(panes[0].position, "0.0");
+
+        // the shell may not have printed yet
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let (pane, capture, content) = capture_pane(socket, "0.0", 10, TMUX_TIMEOUT).unwrap();
+            assert_eq!(pane, panes[0]);
+            assert_eq!(capture.socket.as_deref(), socket);
+            if content.contains("hello from the pane") {
+                break;
+            }
+            assert!(Instant::now() < deadline, "captured {:?}", content);
+            thread::sleep(Duration::from_millis(50));
+        }
+        assert!(capture_pane(socket, "%99", 10, TMUX_TIMEOUT).is_err());
+    }
+}
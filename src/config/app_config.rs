@@ -0,0 +1,166 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{Result, eyre::WrapErr};
+use serde::Deserialize;
+
+const APP_DIR: &str = "nask";
+const CONFIG_FILE: &str = "config.toml";
+
+fn xdg_dir(var: &str, home_fallback: &str) -> Option<PathBuf> {
+    match env::var_os(var) {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => env::var_os("HOME").map(|home| Path::new(&home).join(home_fallback)),
+    }
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config").map(|d| d.join(APP_DIR))
+}
+
+pub fn state_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_STATE_HOME", ".local/state").map(|d| d.join(APP_DIR))
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    pub size: usize,
+    pub dedup: bool,
+    pub persist: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            size: 1000,
+            dedup: true,
+            persist: true,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct EditorConfig {
+    pub submit_on_exit: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasteConfig {
+    /// Pastes with more lines than this become a context entry, `0` always pastes inline.
+    pub attach_threshold_lines: usize,
+}
+
+impl Default for PasteConfig {
+    fn default() -> Self {
+        Self {
+            attach_threshold_lines: 40,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MouseConfig {
+    /// Disable to keep the terminal's native text selection.
+    pub enabled: bool,
+}
+
+impl Default for MouseConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    /// `dark`, `light` or a theme file `themes/<name>.toml` next to this config.
+    pub name: String,
+    /// Role overrides such as `accent = "#ff8800"`.
+    pub colors: BTreeMap<String, String>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            name: String::from("dark"),
+            colors: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    #[default]
+    Auto,
+    Truecolor,
+    #[serde(rename = "256")]
+    Ansi256,
+    #[serde(rename = "16")]
+    Ansi16,
+    None,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayConfig {
+    /// `auto` looks at NO_COLOR, COLORTERM and TERM.
+    pub colors: ColorMode,
+    /// Plain ASCII banner, borders and markers for fonts without box drawing characters.
+    pub ascii: bool,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TmuxConfig {
+    /// `-L` socket name of the server to pick panes from, without one the server nask runs in.
+    pub socket: Option<String>,
+}
+
+/// Key binding overrides per scope, vim notation to action name, e.g. `"<C-c>" = "quit"`.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeysConfig {
+    pub global: BTreeMap<String, String>,
+    pub input: BTreeMap<String, String>,
+    pub input_normal: BTreeMap<String, String>,
+    pub input_insert: BTreeMap<String, String>,
+    pub chat: BTreeMap<String, String>,
+    pub contexts: BTreeMap<String, String>,
+    pub operator_pending: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub history: HistoryConfig,
+    pub editor: EditorConfig,
+    pub paste: PasteConfig,
+    pub mouse: MouseConfig,
+    pub keys: KeysConfig,
+    pub theme: ThemeConfig,
+    pub display: DisplayConfig,
+    pub tmux: TmuxConfig,
+}
+
+impl AppConfig {
+    pub fn load() -> Result<Self> {
+        let Some(path) = config_dir().map(|d| d.join(CONFIG_FILE)) else {
+            return Ok(Self::default());
+        };
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let raw = fs::read_to_string(&path)
+            .wrap_err_with(|| format!("reading config {}", path.display()))?;
+        toml::from_str(&raw).wrap_err_with(|| format!("parsing config {}", path.display()))
+    }
+}
@@ -1,32 +1,220 @@
+use crate::back_logic::cargo::{CargoCheck, Diagnostic, SourceStamp};
+use crate::back_logic::git::GitContext;
 use crate::back_logic::message_loop::Command;
+use crate::back_logic::project_files::CACHE_TTL;
+use crate::back_logic::shell::ShellOutput;
+use crate::back_logic::tmux::{TmuxCapture, TmuxPane};
+use crate::ui::cargo_diagnostics::cargo_finished;
+use crate::ui::chat_search::ChatSearch;
+use crate::ui::completion::Completion;
+use crate::ui::git_context::git_finished;
+use crate::ui::layout::AppLayout;
+use crate::ui::list_view::wrap_step;
+use crate::ui::nask_center_input::send_pending_prompts;
+use crate::ui::overlay::{OverlayStack, Toast};
+use crate::ui::prompt_history::{HistorySearch, PromptHistory};
+use crate::ui::theme::Theme;
+use crate::ui::tmux_panes::{tmux_captured, tmux_panes_listed};
+use crate::ui::vim_motions::VimState;
+use crossterm::event::KeyEvent;
+use ratatui::{layout::Rect, text::Line};
 use tui_input::Input;
 
-use std::{sync::mpsc, time::SystemTime};
+use std::{
+    cell::{Cell, RefCell},
+    sync::Arc,
+    sync::atomic::{AtomicU64, Ordering},
+    sync::mpsc,
+    time::{Duration, Instant, SystemTime},
+};
 
 #[derive(Clone)]
 pub struct UiSink {
     pub tx: mpsc::Sender<UiEvent>,
 }
 
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenUsage {
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+}
+
+impl TokenUsage {
+    pub fn total(&self) -> usize {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
 pub enum UiEvent {
-    ChatAnswer { text: String, more_follows: bool },
+    ChatAnswer {
+        text: String,
+        more_follows: bool,
+    },
+    GenerationStarted,
+    GenerationFinished {
+        usage: TokenUsage,
+    },
+    ShellFinished {
+        output: ShellOutput,
+    },
+    GitFinished {
+        git: GitContext,
+        review: bool,
+        output: Result<String, String>,
+    },
+    CargoFinished {
+        cargo: CargoCheck,
+        fix: bool,
+        diagnostics: Result<Vec<Diagnostic>, String>,
+        built: Option<SourceStamp>,
+    },
+    TmuxPanesListed {
+        socket: Option<String>,
+        panes: Result<Vec<TmuxPane>, String>,
+    },
+    TmuxCaptured {
+        output: Result<(TmuxPane, TmuxCapture, String), String>,
+    },
+    ProjectFilesWalked {
+        files: Arc<[String]>,
+    },
+    ContextsRefreshed {
+        results: Vec<(ContextSource, Result<String, String>)>,
+        cargo_built: Option<SourceStamp>,
+    },
 }
 
 impl UiSink {
     pub fn chat_answer(&self, text: String, more_follows: bool) {
         let _ = self.tx.send(UiEvent::ChatAnswer { text, more_follows });
     }
+
+    pub fn generation_started(&self) {
+        let _ = self.tx.send(UiEvent::GenerationStarted);
+    }
+
+    pub fn generation_finished(&self, usage: TokenUsage) {
+        let _ = self.tx.send(UiEvent::GenerationFinished { usage });
+    }
+
+    pub fn shell_finished(&self, output: ShellOutput) {
+        let _ = self.tx.send(UiEvent::ShellFinished { output });
+    }
+
+    pub fn git_finished(&self, git: GitContext, review: bool, output: Result<String, String>) {
+        let _ = self.tx.send(UiEvent::GitFinished {
+            git,
+            review,
+            output,
+        });
+    }
+
+    pub fn cargo_finished(
+        &self,
+        cargo: CargoCheck,
+        fix: bool,
+        diagnostics: Result<Vec<Diagnostic>, String>,
+        built: Option<SourceStamp>,
+    ) {
+        let _ = self.tx.send(UiEvent::CargoFinished {
+            cargo,
+            fix,
+            diagnostics,
+            built,
+        });
+    }
+
+    pub fn tmux_panes_listed(&self, socket: Option<String>, panes: Result<Vec<TmuxPane>, String>) {
+        let _ = self.tx.send(UiEvent::TmuxPanesListed { socket, panes });
+    }
+
+    pub fn tmux_captured(&self, output: Result<(TmuxPane, TmuxCapture, String), String>) {
+        let _ = self.tx.send(UiEvent::TmuxCaptured { output });
+    }
+
+    pub fn project_files_walked(&self, files: Arc<[String]>) {
+        let _ = self.tx.send(UiEvent::ProjectFilesWalked { files });
+    }
+
+    pub fn contexts_refreshed(
+        &self,
+        results: Vec<(ContextSource, Result<String, String>)>,
+        cargo_built: Option<SourceStamp>,
+    ) {
+        let _ = self.tx.send(UiEvent::ContextsRefreshed {
+            results,
+            cargo_built,
+        });
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContextSource {
+    Nvim,
+    File,
+    Git(GitContext),
+    Cargo(CargoCheck),
+    Shell(String), // the command line, output is only captured when the user runs it
+    Tmux(TmuxCapture),
+    Paste,
+}
+
+impl ContextSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ContextSource::Nvim => "nvim",
+            ContextSource::File => "file",
+            ContextSource::Git(_) => "git",
+            ContextSource::Cargo(_) => "cargo",
+            ContextSource::Shell(_) => "shell",
+            ContextSource::Tmux(_) => "tmux",
+            ContextSource::Paste => "paste",
+        }
+    }
+
+    /// Runs a git, cargo or tmux context again, `None` for contexts that are not computed.
+    /// Blocks until the command is done or `timeout` passed.
+    pub fn refresh(&self, timeout: Duration) -> Option<color_eyre::Result<String>> {
+        match self {
+            ContextSource::Git(git) => Some(git.run(timeout)),
+            ContextSource::Cargo(cargo) => Some(cargo.run(timeout)),
+            ContextSource::Tmux(capture) => Some(capture.run(timeout)),
+            _ => None,
+        }
+    }
+
+    pub fn is_computed(&self) -> bool {
+        matches!(
+            self,
+            ContextSource::Git(_) | ContextSource::Cargo(_) | ContextSource::Tmux(_)
+        )
+    }
 }
 
 pub struct CheckBoxEntry {
     pub checked: bool,
     pub selected: bool,
     pub entry: String,
+    pub content: Option<String>,
+    pub source: ContextSource,
+}
+
+impl CheckBoxEntry {
+    pub fn line_count(&self) -> Option<usize> {
+        self.content.as_ref().map(|c| c.lines().count())
+    }
+
+    // rough rule of thumb, about four characters per token
+    pub fn estimated_tokens(&self) -> Option<usize> {
+        self.content.as_ref().map(|c| c.chars().count().div_ceil(4))
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum Focus {
     Input,
+    Chat,
+    Contexts,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -35,19 +223,40 @@ pub enum InputMode {
     Insert,
 }
 
+pub struct ContextFilter {
+    pub query: String,
+    pub editing: bool,
+}
+
 pub struct AdditionalContextState {
     pub entries: Vec<CheckBoxEntry>,
     pub collapsed: bool,
+    pub filter: Option<ContextFilter>,
+    pub scroll: usize,             // first visible row of the expanded panel
+    pub refreshing: bool,          // computed contexts are re-run before prompts go out
+    pub fresh: Vec<ContextSource>, // computed just now, the next prompt takes them as they are
+    pub cargo_built: Vec<(CargoCheck, SourceStamp)>, // the sources behind each cargo output
+}
+
+/// The last walk of the working directory, see `AppUIState::project_files`.
+#[derive(Default)]
+pub struct ProjectFiles {
+    pub files: Arc<[String]>,
+    pub walked: Option<Instant>,
+    pub walking: bool, // a walk is running in the background
 }
 
 pub struct NaskInputBoxState {
     pub input: Input,
-    pub focus: Focus,
     pub mode: InputMode,
     pub input_scroll: u16,
     pub last_input_inner_width: u16,
     pub cursor_pos: Option<(u16, u16)>,
     pub last_cursor_pos: Option<(u16, u16)>,
+    pub vim: VimState,
+    pub history: PromptHistory,
+    pub history_search: Option<HistorySearch>,
+    pub completion: Option<Completion>,
 }
 
 pub struct MetaInfoState {
@@ -55,33 +264,182 @@ pub struct MetaInfoState {
     pub endpoint: String,
 }
 
+// styled lines of a message, valid for one width and theme
+struct RenderedLines {
+    width: u16,
+    theme: Theme,
+    lines: Vec<Line<'static>>,
+}
+
 pub struct ChatMessage {
+    #[allow(dead_code)]
     pub timestamp: SystemTime,
     pub is_response: bool,
     pub message: String,
     pub is_complete: bool,
+    pub is_error: bool,
+    id: u64, // unique for the session, tells the transcript's height cache apart
+    layout_cache: Cell<Option<(u16, u16)>>, // (width, wrapped height) of the last render
+    lines_cache: RefCell<Option<RenderedLines>>,
 }
 
 impl ChatMessage {
     pub fn new(response: bool, message: String) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
         Self {
             timestamp: SystemTime::now(),
             is_response: response,
             message,
             is_complete: false,
+            is_error: false,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            layout_cache: Cell::new(None),
+            lines_cache: RefCell::new(None),
+        }
+    }
+
+    /// Wrapped height at `width`, measured once per width and text.
+    pub fn cached_height(&self, width: u16, measure: impl FnOnce(&Self) -> u16) -> u16 {
+        match self.layout_cache.get() {
+            Some((cached_width, height)) if cached_width == width => height,
+            _ => {
+                let height = measure(self);
+                self.layout_cache.set(Some((width, height)));
+                height
+            }
+        }
+    }
+
+    /// Styled lines, rendered once per width, theme and text.
+    pub fn cached_lines(
+        &self,
+        width: u16,
+        theme: &Theme,
+        render: impl FnOnce(&Self) -> Vec<Line<'static>>,
+    ) -> Vec<Line<'static>> {
+        let mut cache = self.lines_cache.borrow_mut();
+        match cache.as_ref() {
+            Some(cached) if cached.width == width && cached.theme == *theme => cached.lines.clone(),
+            _ => {
+                let lines = render(self);
+                *cache = Some(RenderedLines {
+                    width,
+                    theme: *theme,
+                    lines: lines.clone(),
+                });
+                lines
+            }
+        }
+    }
+
+    // to be called whenever text or completion change
+    pub fn invalidate_layout(&self) {
+        self.layout_cache.set(None);
+        self.lines_cache.replace(None);
+    }
+
+    pub fn prompt(message: String) -> Self {
+        Self {
+            is_complete: true,
+            ..Self::new(false, message)
+        }
+    }
+
+    pub fn error(message: String) -> Self {
+        Self {
+            is_complete: true,
+            is_error: true,
+            ..Self::new(true, message)
         }
     }
 }
 
 pub struct ChatState {
     pub chat_messages: Vec<ChatMessage>,
+    pub scroll_offset: u16, // lines scrolled up from the newest message
+    pub search: Option<ChatSearch>,
+    pub selected: Option<usize>,
+    pub resend_from: Option<usize>, // prompt being edited, replaced on the next submit
+    pub reveal: Option<(usize, usize)>, // (message, byte offset) the renderer scrolls into view
+    pub system_prompt: Option<String>,
+    pub pending_prompts: Vec<String>, // sent once the computed contexts are refreshed
+    // summed height of all but the newest message: (width, count, id of the last one summed, height)
+    pub older_height: Cell<Option<(u16, usize, u64, u32)>>,
+}
+
+impl ChatState {
+    /// Height of the whole transcript at `width`. Only the newest message changes while it
+    /// streams, the ones before it are summed once. Messages are only added at the end and
+    /// never come back once removed, so the count and the id of the last summed message tell
+    /// whether that sum still holds.
+    pub fn total_height(&self, width: u16, message_height: impl Fn(&ChatMessage) -> u16) -> u32 {
+        let Some((newest, older)) = self.chat_messages.split_last() else {
+            return 0;
+        };
+        let key = (width, older.len(), older.last().map_or(u64::MAX, |m| m.id));
+        let older_height = match self.older_height.get() {
+            Some((w, count, id, height)) if (w, count, id) == key => height,
+            _ => {
+                let height = older.iter().map(|m| message_height(m) as u32).sum();
+                self.older_height.set(Some((key.0, key.1, key.2, height)));
+                height
+            }
+        };
+        older_height + message_height(newest) as u32
+    }
+}
+
+pub struct FinishedGeneration {
+    pub elapsed: Duration,
+    pub completion_tokens: usize,
+}
+
+/// What the status line reports about requests to the model.
+#[derive(Default)]
+pub struct GenerationState {
+    pub started: Option<Instant>, // set while a request is in flight
+    pub streamed_chars: usize,
+    pub last: Option<FinishedGeneration>,
+    pub session_usage: TokenUsage,
+}
+
+impl GenerationState {
+    pub fn elapsed(&self) -> Option<Duration> {
+        match (self.started, &self.last) {
+            (Some(started), _) => Some(started.elapsed()),
+            (None, Some(last)) => Some(last.elapsed),
+            (None, None) => None,
+        }
+    }
+
+    /// Live estimate while streaming, the reported count once finished.
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        let (tokens, elapsed) = match (self.started, &self.last) {
+            (Some(started), _) => (self.streamed_chars.div_ceil(4), started.elapsed()),
+            (None, Some(last)) => (last.completion_tokens, last.elapsed),
+            (None, None) => return None,
+        };
+        let secs = elapsed.as_secs_f64();
+        (secs > 0.0).then(|| tokens as f64 / secs)
+    }
 }
 
 pub struct AppUIState {
+    pub focus: Focus,
+    pub theme: Theme,
+    pub overlays: OverlayStack,
     pub input_box_state: NaskInputBoxState,
     pub meta_info_state: MetaInfoState,
     pub additional_context_state: AdditionalContextState,
     pub chat_state: ChatState,
+    pub generation: GenerationState,
+    pub pending_keys: Vec<KeyEvent>,
+    pub frame_area: Rect,
+    pub layout: AppLayout,
+    pub dirty: bool, // something changed since the last frame was drawn
+    pub tmux_socket: Option<String>, // `-L` server for the tmux panes, `None` for nask's own
+    pub project_files: ProjectFiles,
 
     pub pump_message_loop: Box<dyn FnMut(Command)>,
 }
@@ -90,6 +448,14 @@ impl Default for ChatState {
     fn default() -> Self {
         Self {
             chat_messages: Vec::new(),
+            scroll_offset: 0,
+            search: None,
+            selected: None,
+            resend_from: None,
+            reveal: None,
+            system_prompt: None,
+            pending_prompts: Vec::new(),
+            older_height: Cell::new(None),
         }
     }
 }
@@ -108,7 +474,126 @@ impl Default for AdditionalContextState {
         Self {
             entries: Vec::new(),
             collapsed: true,
+            filter: None,
+            scroll: 0,
+            refreshing: false,
+            fresh: Vec::new(),
+            cargo_built: Vec::new(),
+        }
+    }
+}
+
+impl AdditionalContextState {
+    pub fn push_entry(&mut self, mut entry: CheckBoxEntry) {
+        entry.selected = !self.entries.iter().any(|e| e.selected);
+        self.entries.push(entry);
+    }
+
+    /// Adds `entry` or, if the same path is already listed, refreshes and checks that one.
+    pub fn attach(&mut self, entry: CheckBoxEntry) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|e| e.entry == entry.entry && e.source == entry.source)
+        {
+            Some(existing) => {
+                existing.content = entry.content;
+                existing.checked = true;
+            }
+            None => self.push_entry(entry),
+        }
+    }
+
+    /// The checked git, cargo and tmux contexts, re-run before a prompt is sent.
+    pub fn computed_sources(&self) -> Vec<ContextSource> {
+        let mut sources: Vec<ContextSource> = Vec::new();
+        for entry in self.entries.iter().filter(|e| e.checked) {
+            if entry.source.is_computed() && !sources.contains(&entry.source) {
+                sources.push(entry.source.clone());
+            }
+        }
+        sources
+    }
+
+    /// Remembers which sources the output of `cargo` was built from, `None` forgets it.
+    pub fn record_cargo_build(&mut self, cargo: CargoCheck, built: Option<SourceStamp>) {
+        self.cargo_built.retain(|(c, _)| *c != cargo);
+        if let Some(built) = built {
+            self.cargo_built.push((cargo, built));
+        }
+    }
+
+    /// Stores the refreshed outputs. A failed or timed out refresh keeps the last output,
+    /// the returned messages say which ones. Skipped cargo contexts have no result.
+    pub fn apply_refreshed(
+        &mut self,
+        results: Vec<(ContextSource, Result<String, String>)>,
+        cargo_built: Option<SourceStamp>,
+    ) -> Vec<String> {
+        let mut failures = Vec::new();
+        for (source, result) in results {
+            if let (ContextSource::Cargo(cargo), Ok(_)) = (&source, &result) {
+                self.record_cargo_build(*cargo, cargo_built);
+            }
+            for entry in self.entries.iter_mut().filter(|e| e.source == source) {
+                match &result {
+                    Ok(content) => entry.content = Some(content.clone()),
+                    Err(e) => {
+                        failures.push(format!("{}: {}, sent the last output", entry.entry, e))
+                    }
+                }
+            }
+        }
+        failures
+    }
+
+    /// Indices of the entries matching the filter, case insensitive.
+    pub fn visible(&self) -> Vec<usize> {
+        let query = self
+            .filter
+            .as_ref()
+            .map(|f| f.query.to_lowercase())
+            .unwrap_or_default();
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.entry.to_lowercase().contains(&query))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.entries.iter().position(|e| e.selected)
+    }
+
+    pub fn select(&mut self, idx: usize) {
+        for (i, entry) in self.entries.iter_mut().enumerate() {
+            entry.selected = i == idx;
+        }
+    }
+
+    /// Moves the selection through the filtered entries, wrapping around.
+    pub fn step_selection(&mut self, backward: bool) {
+        let visible = self.visible();
+        if visible.is_empty() {
+            return;
         }
+        let next = self
+            .selected()
+            .and_then(|sel| visible.iter().position(|idx| *idx == sel))
+            .map_or(0, |pos| wrap_step(pos, visible.len(), backward));
+        self.select(visible[next]);
+    }
+
+    // keeps the selection on a matching entry after the filter changed
+    pub fn refilter(&mut self) {
+        let visible = self.visible();
+        if let Some(first) = visible.first()
+            && !self.selected().is_some_and(|sel| visible.contains(&sel))
+        {
+            self.select(*first);
+        }
+        self.scroll = 0;
     }
 }
 
@@ -116,12 +601,15 @@ impl Default for NaskInputBoxState {
     fn default() -> Self {
         Self {
             input: Input::default(),
-            focus: Focus::Input,
             mode: InputMode::Insert,
             input_scroll: 0,
             last_input_inner_width: 0,
             cursor_pos: None,
             last_cursor_pos: None,
+            vim: VimState::default(),
+            history: PromptHistory::default(),
+            history_search: None,
+            completion: None,
         }
     }
 }
@@ -129,20 +617,93 @@ impl Default for NaskInputBoxState {
 impl AppUIState {
     pub fn new(pump: impl FnMut(Command) + 'static) -> Self {
         Self {
+            focus: Focus::Input,
+            theme: Theme::default(),
+            overlays: OverlayStack::default(),
             input_box_state: NaskInputBoxState::default(),
             meta_info_state: MetaInfoState::default(),
             additional_context_state: AdditionalContextState::default(),
             chat_state: ChatState::default(),
+            generation: GenerationState::default(),
+            pending_keys: Vec::new(),
+            frame_area: Rect::default(),
+            layout: AppLayout::default(),
+            dirty: true,
+            tmux_socket: None,
+            project_files: ProjectFiles::default(),
             pump_message_loop: Box::new(pump),
         }
     }
 
+    /// The project files as last walked, empty until the first walk is done. Once they are
+    /// `CACHE_TTL` old a new walk starts in the background.
+    pub fn project_files(&mut self) -> Arc<[String]> {
+        let project = &mut self.project_files;
+        if !project.walking
+            && project
+                .walked
+                .is_none_or(|walked| walked.elapsed() >= CACHE_TTL)
+        {
+            project.walking = true;
+            (self.pump_message_loop)(Command::WalkProjectFiles);
+        }
+        Arc::clone(&self.project_files.files)
+    }
+
+    /// A prompt is on its way: waiting for its contexts or for the answer.
+    pub fn prompt_in_flight(&self) -> bool {
+        self.generation.started.is_some()
+            || self.additional_context_state.refreshing
+            || !self.chat_state.pending_prompts.is_empty()
+    }
+
+    pub fn set_focus(&mut self, focus: Focus) {
+        match focus {
+            Focus::Input => {}
+            // the chat pane works on a selected message, start at the newest one
+            Focus::Chat => {
+                let chat_state = &mut self.chat_state;
+                if self.focus != Focus::Chat || chat_state.selected.is_none() {
+                    chat_state.selected = chat_state.chat_messages.len().checked_sub(1);
+                    chat_state.reveal = chat_state.selected.map(|idx| (idx, 0));
+                }
+            }
+            Focus::Contexts => self.additional_context_state.collapsed = false,
+        }
+        if focus != Focus::Chat {
+            self.chat_state.selected = None;
+        }
+        self.focus = focus;
+    }
+
+    /// Tab order is input -> chat -> contexts, the chat is skipped while it is empty.
+    pub fn cycle_focus(&mut self, backward: bool) {
+        const ORDER: [Focus; 3] = [Focus::Input, Focus::Chat, Focus::Contexts];
+
+        let has_chat = !self.chat_state.chat_messages.is_empty();
+        let current = ORDER.iter().position(|f| *f == self.focus).unwrap_or(0);
+        let mut idx = current;
+        loop {
+            idx = if backward {
+                (idx + ORDER.len() - 1) % ORDER.len()
+            } else {
+                (idx + 1) % ORDER.len()
+            };
+            if ORDER[idx] != Focus::Chat || has_chat {
+                break;
+            }
+        }
+        self.set_focus(ORDER[idx]);
+    }
+
     pub fn apply_ui_event(&mut self, ev: UiEvent) {
+        self.dirty = true;
         match ev {
             UiEvent::ChatAnswer { text, more_follows } => {
                 if text.is_empty() {
                     return; // TODO: find out why initially a message get's pushed
                 }
+                self.generation.streamed_chars += text.chars().count();
 
                 let start_new = self
                     .chat_state
@@ -161,7 +722,77 @@ impl AppUIState {
 
                 if let Some(last) = self.chat_state.chat_messages.last_mut() {
                     last.is_complete = !more_follows;
+                    last.invalidate_layout();
+                }
+            }
+            UiEvent::GenerationStarted => {
+                self.generation.started = Some(Instant::now());
+                self.generation.streamed_chars = 0;
+            }
+            UiEvent::GenerationFinished { usage } => {
+                let generation = &mut self.generation;
+                if let Some(started) = generation.started.take() {
+                    generation.last = Some(FinishedGeneration {
+                        elapsed: started.elapsed(),
+                        completion_tokens: usage.completion_tokens,
+                    });
+                }
+                generation.session_usage.prompt_tokens += usage.prompt_tokens;
+                generation.session_usage.completion_tokens += usage.completion_tokens;
+
+                // whatever streamed last is all there is
+                if let Some(last) = self.chat_state.chat_messages.last_mut()
+                    && last.is_response
+                {
+                    last.is_complete = true;
+                    last.invalidate_layout();
+                }
+            }
+            UiEvent::ShellFinished { output } => {
+                let title = output.title();
+                let source = ContextSource::Shell(output.command);
+                // a rerun replaces the previous output of the same command
+                let context_state = &mut self.additional_context_state;
+                context_state.entries.retain(|e| e.source != source);
+                context_state.push_entry(CheckBoxEntry {
+                    checked: true,
+                    selected: false,
+                    entry: title.clone(),
+                    content: Some(output.output),
+                    source,
+                });
+                self.overlays.push(Toast::new(title));
+            }
+            UiEvent::GitFinished {
+                git,
+                review,
+                output,
+            } => git_finished(self, git, review, output),
+            UiEvent::CargoFinished {
+                cargo,
+                fix,
+                diagnostics,
+                built,
+            } => cargo_finished(self, cargo, fix, diagnostics, built),
+            UiEvent::TmuxPanesListed { socket, panes } => tmux_panes_listed(self, socket, panes),
+            UiEvent::TmuxCaptured { output } => tmux_captured(self, output),
+            UiEvent::ProjectFilesWalked { files } => {
+                self.project_files = ProjectFiles {
+                    files,
+                    walked: Some(Instant::now()),
+                    walking: false,
+                };
+            }
+            UiEvent::ContextsRefreshed {
+                results,
+                cargo_built,
+            } => {
+                let context_state = &mut self.additional_context_state;
+                context_state.refreshing = false;
+                for failure in context_state.apply_refreshed(results, cargo_built) {
+                    self.overlays.push(Toast::new(failure));
                 }
+                send_pending_prompts(self);
             }
         }
     }
@@ -0,0 +1,78 @@
+use color_eyre::Result;
+
+use crate::{
+    back_logic::cargo::{CargoCheck, Diagnostic, SourceStamp},
+    ui::{
+        app_ui_state::{AppUIState, ChatMessage, CheckBoxEntry, ContextSource},
+        nask_center_input::send_prompt,
+        overlay::Toast,
+    },
+};
+
+impl CargoCheck {
+    /// A checked context entry holding `diagnostics`.
+    pub fn entry(self, diagnostics: &[Diagnostic]) -> CheckBoxEntry {
+        CheckBoxEntry {
+            checked: true,
+            selected: false,
+            entry: self.title().to_string(),
+            content: Some(self.format(diagnostics)),
+            source: ContextSource::Cargo(self),
+        }
+    }
+}
+
+/// Attaches the result of `/cargo` or `/fix`, for `/fix` the first error is sent to the model.
+pub fn cargo_finished(
+    state: &mut AppUIState,
+    cargo: CargoCheck,
+    fix: bool,
+    diagnostics: Result<Vec<Diagnostic>, String>,
+    built: Option<SourceStamp>,
+) {
+    let command = if fix { "fix" } else { "cargo" };
+    let diagnostics = match diagnostics {
+        Ok(diagnostics) => diagnostics,
+        Err(e) => {
+            state
+                .chat_state
+                .chat_messages
+                .push(ChatMessage::error(format!("/{}: {}", command, e)));
+            return;
+        }
+    };
+    let first_error = diagnostics.iter().find(|d| d.is_error());
+    if fix && first_error.is_none() {
+        state
+            .chat_state
+            .chat_messages
+            .push(ChatMessage::error(format!(
+                "/fix: {} reports no errors",
+                cargo.title()
+            )));
+        return;
+    }
+    let prompt = first_error
+        .filter(|_| fix)
+        .map(|error| match error.location() {
+            Some(location) => format!("Fix this error at {}: {}", location, error.message),
+            None => format!("Fix this error: {}", error.message),
+        });
+    let context_state = &mut state.additional_context_state;
+    context_state.attach(cargo.entry(&diagnostics));
+    context_state.record_cargo_build(cargo, built);
+
+    let Some(prompt) = prompt else {
+        state.overlays.push(Toast::new(format!(
+            "attached {}, {} messages",
+            cargo.title(),
+            diagnostics.len()
+        )));
+        return;
+    };
+    state
+        .chat_state
+        .chat_messages
+        .push(ChatMessage::prompt(prompt.clone()));
+    send_prompt(state, prompt);
+}
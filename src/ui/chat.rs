@@ -0,0 +1,582 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Paragraph, Widget, Wrap},
+};
+use tui_input::Input;
+
+use crate::ui::{
+    app_ui_state::{AppUIState, ChatMessage, ChatState, Focus, InputMode},
+    chat_search::ChatSearch,
+    clipboard::{copy_to_clipboard, extract_code_blocks},
+    event_system::{EventSignal, KeyOperationEvent},
+    nask_center_input::{clamp_input_scroll, create_input_box, send_prompt},
+    overlay::Toast,
+    renderable_trait::Renderable,
+    theme::Theme,
+};
+
+#[derive(Clone, Copy)]
+pub struct NaskChat {
+    pub chat_dialog: &'static dyn Renderable,
+    pub input_box: &'static dyn Renderable,
+}
+
+struct ChatDialog;
+
+impl ChatDialog {
+    fn estimate_wrapped_lines_asciiish(s: &str, width: u16) -> u16 {
+        if width == 0 {
+            return 0;
+        }
+        let w = width as usize;
+
+        let mut lines: u16 = 0;
+        for raw_line in s.split('\n') {
+            // Keep empty lines
+            if raw_line.is_empty() {
+                lines = lines.saturating_add(1);
+                continue;
+            }
+
+            let mut col: usize = 0;
+
+            for word in raw_line.split_whitespace() {
+                // Count "columns" as number of chars (not bytes)
+                let word_len = word.chars().count();
+                let extra = if col == 0 { word_len } else { 1 + word_len };
+
+                if col + extra <= w {
+                    col += extra;
+                } else {
+                    // new visual line
+                    lines = lines.saturating_add(1);
+                    col = word_len;
+
+                    // If a single word is longer than width, hard-break it
+                    if col > w {
+                        // number of lines needed for this word
+                        let full = col / w;
+                        let rem = col % w;
+                        lines = lines.saturating_add(full as u16);
+                        col = rem;
+                        if col == 0 {
+                            // exactly ended on boundary; next word starts fresh
+                            col = 0;
+                        }
+                    }
+                }
+            }
+
+            // final line for this raw_line
+            lines = lines.saturating_add(1);
+        }
+
+        lines.max(1)
+    }
+
+    fn message_height(msg: &ChatMessage, area_width: u16) -> u16 {
+        msg.cached_height(area_width, |msg| ChatDialog::measure(msg, area_width))
+    }
+
+    fn measure(msg: &ChatMessage, area_width: u16) -> u16 {
+        let has_border = !msg.is_response;
+        let inner_w = area_width
+            .saturating_sub(if has_border { 2 } else { 0 })
+            .max(1);
+
+        let mut inner_lines = ChatDialog::estimate_wrapped_lines_asciiish(&msg.message, inner_w);
+
+        if !msg.is_complete {
+            inner_lines = inner_lines.saturating_add(1); // for the ellipsis
+        }
+
+        inner_lines.saturating_add(if has_border { 2 } else { 0 })
+    }
+
+    /// Scrolls so the position requested in `ChatState::reveal` is on screen, roughly centered.
+    fn reveal_requested_position(chat_state: &mut ChatState, area: Rect) {
+        let Some((message_idx, byte_offset)) = chat_state.reveal.take() else {
+            return;
+        };
+
+        let messages = &chat_state.chat_messages;
+        let Some(msg) = messages.get(message_idx) else {
+            return;
+        };
+
+        let has_border = !msg.is_response;
+        let border = if has_border { 1 } else { 0 };
+        let inner_w = area.width.saturating_sub(border * 2).max(1);
+
+        // the line inside the message the position is on
+        let hit_line = border
+            + ChatDialog::estimate_wrapped_lines_asciiish(&msg.message[..byte_offset], inner_w)
+                .saturating_sub(1);
+
+        let below: u32 = messages[message_idx + 1..]
+            .iter()
+            .map(|m| ChatDialog::message_height(m, area.width) as u32)
+            .sum();
+        let msg_height = ChatDialog::message_height(msg, area.width);
+        let from_bottom = below + msg_height.saturating_sub(hit_line + 1) as u32;
+
+        let visible =
+            chat_state.scroll_offset as u32..(chat_state.scroll_offset + area.height) as u32;
+        if !visible.contains(&from_bottom) {
+            let centered = from_bottom.saturating_sub(area.height as u32 / 2);
+            chat_state.scroll_offset = centered.min(u16::MAX as u32) as u16;
+        }
+    }
+}
+
+// (start, end, is current) of the search hits in one message
+fn search_hits(msg_idx: usize, search: Option<&ChatSearch>) -> Vec<(usize, usize, bool)> {
+    search
+        .map(|search| {
+            search
+                .hits
+                .iter()
+                .enumerate()
+                .filter(|(_, hit)| hit.message_idx == msg_idx)
+                .map(|(idx, hit)| (hit.start, hit.end, Some(idx) == search.current))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn highlighted_lines(
+    msg: &ChatMessage,
+    hits: &[(usize, usize, bool)],
+    theme: &Theme,
+) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut line_start = 0;
+    let mut in_code_block = false;
+    for raw_line in msg.message.split('\n') {
+        let line_end = line_start + raw_line.len();
+        let is_fence = raw_line.trim_start().starts_with("```");
+        if is_fence {
+            in_code_block = !in_code_block;
+        }
+        let mut spans = Vec::new();
+        let mut pos = line_start;
+
+        for &(start, end, is_current) in hits
+            .iter()
+            .filter(|(s, _, _)| (line_start..line_end).contains(s))
+        {
+            if start > pos {
+                spans.push(Span::raw(msg.message[pos..start].to_string()));
+            }
+            let style = if is_current && theme.accent == Color::Reset {
+                // no colors (NO_COLOR), tell the current hit apart by its weight
+                Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD)
+            } else if is_current {
+                Style::default().fg(theme.surface).bg(theme.accent)
+            } else {
+                Style::default().add_modifier(Modifier::REVERSED)
+            };
+            let end = end.min(line_end);
+            spans.push(Span::styled(msg.message[start..end].to_string(), style));
+            pos = end;
+        }
+        if pos < line_end {
+            spans.push(Span::raw(msg.message[pos..line_end].to_string()));
+        }
+
+        let mut line = Line::from(spans);
+        if in_code_block || is_fence {
+            line = line.style(Style::default().bg(theme.code_background));
+        }
+        lines.push(line);
+        line_start = line_end + 1;
+    }
+    if !msg.is_complete {
+        lines.push(Line::from(Span::styled(
+            theme.glyphs.ellipsis,
+            Style::default().fg(theme.dim).add_modifier(Modifier::DIM),
+        )));
+    }
+    lines
+}
+
+pub fn scroll_chat(state: &mut AppUIState, lines_up: i32) {
+    let offset = state.chat_state.scroll_offset as i32 + lines_up;
+    // the upper bound depends on the wrapped transcript and is clamped while rendering
+    state.chat_state.scroll_offset = offset.clamp(0, u16::MAX as i32) as u16;
+}
+
+fn selected_message(state: &AppUIState) -> Option<&ChatMessage> {
+    let chat_state = &state.chat_state;
+    chat_state
+        .selected
+        .and_then(|idx| chat_state.chat_messages.get(idx))
+}
+
+// accent bar left of the transcript while the chat pane has focus
+fn render_focus_marker(area: Rect, buf: &mut Buffer, theme: &Theme) {
+    let Some(x) = area.x.checked_sub(1) else {
+        return;
+    };
+    for y in area.top()..area.bottom() {
+        if let Some(cell) = buf.cell_mut((x, y)) {
+            cell.set_symbol(theme.glyphs.focus_marker)
+                .set_fg(theme.accent);
+        }
+    }
+}
+
+impl Renderable for ChatDialog {
+    // the layout already leaves room around the transcript
+    fn area_rect(&self, area: Rect) -> Rect {
+        area
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, state: &mut AppUIState) {
+        let total_height = state.chat_state.total_height(area.width, |msg| {
+            ChatDialog::message_height(msg, area.width)
+        });
+        let max_scroll = total_height.saturating_sub(area.height as u32) as u16;
+        ChatDialog::reveal_requested_position(&mut state.chat_state, area);
+        state.chat_state.scroll_offset = state.chat_state.scroll_offset.min(max_scroll);
+
+        if state.focus == Focus::Chat {
+            render_focus_marker(area, buf, &state.theme);
+        }
+
+        let theme = &state.theme;
+        let chat_state = &state.chat_state;
+        let area_top = area.y as i32;
+        let area_bottom = area.bottom() as i32;
+
+        // render newest at bottom, shifted down by the scroll offset
+        let mut y_bottom = area_bottom + chat_state.scroll_offset as i32;
+
+        for (msg_idx, msg) in chat_state.chat_messages.iter().enumerate().rev() {
+            if y_bottom <= area_top {
+                break;
+            }
+
+            let h = ChatDialog::message_height(msg, area.width) as i32;
+            if h == 0 {
+                continue;
+            }
+
+            let top = y_bottom - h;
+            let visible_top = top.max(area_top);
+            let visible_bottom = y_bottom.min(area_bottom);
+            y_bottom = top;
+
+            if visible_bottom <= visible_top {
+                continue;
+            }
+
+            let msg_area = Rect {
+                x: area.x,
+                y: visible_top as u16,
+                width: area.width,
+                height: (visible_bottom - visible_top) as u16,
+            };
+
+            let (block, base_style) = if msg.is_error {
+                (None, Style::default().fg(theme.error))
+            } else if msg.is_response {
+                (
+                    None,
+                    Style::default()
+                        .fg(theme.assistant_message)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                let b = Block::default()
+                    .borders(Borders::ALL)
+                    .border_set(theme.glyphs.border)
+                    .border_style(Style::default().fg(theme.user_message));
+                (Some(b), Style::default())
+            };
+
+            // search hits change with every keystroke, only plain messages are cached
+            let hits = search_hits(msg_idx, chat_state.search.as_ref());
+            let text = Text::from(if hits.is_empty() {
+                msg.cached_lines(area.width, theme, |msg| {
+                    highlighted_lines(msg, &hits, theme)
+                })
+            } else {
+                highlighted_lines(msg, &hits, theme)
+            });
+
+            let mut p = Paragraph::new(text)
+                .style(base_style)
+                .wrap(Wrap { trim: false })
+                .scroll(((visible_top - top) as u16, 0));
+
+            if chat_state.selected == Some(msg_idx) {
+                p = p.style(base_style.patch(theme.selection_style()));
+            }
+            if let Some(b) = block {
+                p = p.block(b);
+            }
+
+            p.render(msg_area, buf);
+        }
+    }
+    fn handle_key_operation(
+        &self,
+        key_operation_event: KeyOperationEvent,
+        key: KeyEvent,
+        state: &mut AppUIState,
+    ) -> Option<EventSignal> {
+        match key_operation_event {
+            KeyOperationEvent::ChatScrollUp => {
+                scroll_chat(state, (state.frame_area.height / 2) as i32);
+            }
+            KeyOperationEvent::ChatScrollDown => {
+                scroll_chat(state, -((state.frame_area.height / 2) as i32));
+            }
+            KeyOperationEvent::ChatSearchForward | KeyOperationEvent::ChatSearchBackward => {
+                let backward = key_operation_event == KeyOperationEvent::ChatSearchBackward;
+                state.chat_state.search = Some(ChatSearch::new(backward));
+            }
+            KeyOperationEvent::ChatSearchNext | KeyOperationEvent::ChatSearchPrev => {
+                if let Some(search) = state.chat_state.search.as_mut() {
+                    search.step(key_operation_event == KeyOperationEvent::ChatSearchPrev);
+                    state.chat_state.reveal = search.reveal_target();
+                }
+            }
+            KeyOperationEvent::ForwardToChatSearch | KeyOperationEvent::ChatSearchBackspace => {
+                let chat_state = &mut state.chat_state;
+                if let Some(search) = chat_state.search.as_mut() {
+                    match key.code {
+                        KeyCode::Char(c) => search.query.push(c),
+                        _ => {
+                            search.query.pop();
+                        }
+                    }
+                    search.update(&chat_state.chat_messages);
+                    chat_state.reveal = search.reveal_target();
+                }
+            }
+            KeyOperationEvent::ChatSearchConfirm => {
+                if let Some(search) = state.chat_state.search.as_mut() {
+                    search.editing = false;
+                }
+                if state
+                    .chat_state
+                    .search
+                    .as_ref()
+                    .is_some_and(|search| search.query.is_empty())
+                {
+                    state.chat_state.search = None;
+                }
+            }
+            KeyOperationEvent::ChatSearchCancel | KeyOperationEvent::ChatSearchClear => {
+                state.chat_state.search = None;
+            }
+            KeyOperationEvent::ChatSelectOlder
+            | KeyOperationEvent::ChatSelectNewer
+            | KeyOperationEvent::ChatSelectOldest
+            | KeyOperationEvent::ChatSelectNewest => {
+                let chat_state = &mut state.chat_state;
+                let last = chat_state.chat_messages.len().saturating_sub(1);
+                let current = chat_state.selected.unwrap_or(last);
+                let idx = match key_operation_event {
+                    KeyOperationEvent::ChatSelectOlder => current.saturating_sub(1),
+                    KeyOperationEvent::ChatSelectNewer => (current + 1).min(last),
+                    KeyOperationEvent::ChatSelectOldest => 0,
+                    _ => last,
+                };
+                chat_state.selected = Some(idx);
+                chat_state.reveal = Some((idx, 0));
+            }
+            KeyOperationEvent::ChatYankMessage | KeyOperationEvent::ChatYankCode => {
+                let Some(msg) = selected_message(state) else {
+                    return Some(EventSignal::Continue);
+                };
+                let text = if key_operation_event == KeyOperationEvent::ChatYankCode {
+                    extract_code_blocks(&msg.message).join("\n\n")
+                } else {
+                    msg.message.clone()
+                };
+                if text.is_empty() {
+                    state.overlays.push(Toast::new("no code blocks to copy"));
+                } else {
+                    copy_to_clipboard(&text);
+                    state
+                        .overlays
+                        .push(Toast::new(format!("copied {} lines", text.lines().count())));
+                    state.input_box_state.vim.register = text;
+                }
+            }
+            KeyOperationEvent::ChatDeleteMessage => {
+                let chat_state = &mut state.chat_state;
+                if let Some(idx) = chat_state.selected {
+                    chat_state.chat_messages.remove(idx);
+                    chat_state.search = None;
+                    if chat_state.resend_from.is_some_and(|from| from >= idx) {
+                        chat_state.resend_from = None;
+                    }
+                    chat_state.selected = match chat_state.chat_messages.len() {
+                        0 => None,
+                        len => Some(idx.min(len - 1)),
+                    };
+                    if chat_state.selected.is_none() {
+                        state.set_focus(Focus::Input);
+                    }
+                }
+            }
+            KeyOperationEvent::ChatRegenerate => {
+                let chat_state = &mut state.chat_state;
+                let Some(idx) = chat_state
+                    .selected
+                    .filter(|idx| chat_state.chat_messages[*idx].is_response)
+                else {
+                    return Some(EventSignal::Continue);
+                };
+                let Some(prompt) = chat_state.chat_messages[..idx]
+                    .iter()
+                    .rev()
+                    .find(|m| !m.is_response)
+                    .map(|m| m.message.clone())
+                else {
+                    return Some(EventSignal::Continue);
+                };
+
+                chat_state.chat_messages.truncate(idx);
+                chat_state.search = None;
+                state.set_focus(Focus::Input);
+                send_prompt(state, prompt);
+            }
+            KeyOperationEvent::ChatEditResend => {
+                let chat_state = &mut state.chat_state;
+                let Some(idx) = chat_state
+                    .selected
+                    .filter(|idx| !chat_state.chat_messages[*idx].is_response)
+                else {
+                    return Some(EventSignal::Continue);
+                };
+
+                let input_box_state = &mut state.input_box_state;
+                input_box_state.input = Input::new(chat_state.chat_messages[idx].message.clone());
+                input_box_state.mode = InputMode::Insert;
+                input_box_state.vim.reset();
+                clamp_input_scroll(input_box_state);
+
+                chat_state.resend_from = Some(idx);
+                state.set_focus(Focus::Input);
+            }
+            _ => return None,
+        }
+        Some(EventSignal::Continue)
+    }
+}
+
+pub fn create_chat_dialog() -> &'static dyn Renderable {
+    static CHAT: ChatDialog = ChatDialog;
+    &CHAT
+}
+impl NaskChat {
+    pub fn new() -> Self {
+        Self {
+            chat_dialog: create_chat_dialog(),
+            input_box: create_input_box(),
+        }
+    }
+}
+
+pub fn create_nask_chat() -> NaskChat {
+    NaskChat::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    const MESSAGES: usize = 10_000;
+    const FRAMES: u32 = 100;
+
+    // run with `cargo test --release -- --ignored --nocapture render_10k`
+    #[test]
+    #[ignore = "benchmark"]
+    fn render_10k_messages() {
+        let mut state = AppUIState::new(|_| {});
+        for idx in 0..MESSAGES {
+            let text = format!(
+                "message {idx}: some prose that wraps at narrow widths\n```rust\nfn f() {{}}\n```"
+            );
+            state.chat_state.chat_messages.push(if idx % 2 == 0 {
+                ChatMessage::prompt(text)
+            } else {
+                ChatMessage::new(true, text)
+            });
+        }
+        let area = Rect::new(0, 0, 120, 40);
+        let mut buf = Buffer::empty(area);
+        let chat = create_chat_dialog();
+        let mut frame = |state: &mut AppUIState| {
+            let started = Instant::now();
+            chat.render(area, &mut buf, state);
+            started.elapsed()
+        };
+
+        let cold = frame(&mut state);
+        let warm: Duration = (0..FRAMES).map(|_| frame(&mut state)).sum::<Duration>() / FRAMES;
+        // streaming into the newest message only re-measures that one
+        let streaming: Duration = (0..FRAMES)
+            .map(|_| {
+                let last = state.chat_state.chat_messages.last_mut().unwrap();
+                last.message.push_str(" more");
+                last.invalidate_layout();
+                frame(&mut state)
+            })
+            .sum::<Duration>()
+            / FRAMES;
+        println!("{MESSAGES} messages: cold {cold:?}, warm {warm:?}, streaming {streaming:?}");
+        assert!(warm < cold);
+    }
+
+    // jumps to the current hit of `search` like `n` does, the new scroll offset
+    fn reveal_current(chat_state: &mut ChatState, search: &ChatSearch, area: Rect) -> u16 {
+        chat_state.reveal = search.reveal_target();
+        ChatDialog::reveal_requested_position(chat_state, area);
+        assert!(chat_state.reveal.is_none());
+        chat_state.scroll_offset
+    }
+
+    #[test]
+    fn search_hits_are_scrolled_into_view() {
+        let mut state = AppUIState::new(|_| {});
+        let chat_state = &mut state.chat_state;
+        // one line each, the newest at the bottom
+        for idx in 0..50 {
+            let mut answer = ChatMessage::new(true, format!("answer {idx}"));
+            answer.is_complete = true;
+            chat_state.chat_messages.push(answer);
+        }
+        let area = Rect::new(0, 0, 40, 10);
+
+        // 42 lines below the hit, centered in the ten visible ones
+        let mut search = ChatSearch::new(true);
+        search.query = String::from("answer 7");
+        search.update(&chat_state.chat_messages);
+        assert_eq!(reveal_current(chat_state, &search, area), 37);
+
+        // "answer 4" is on screen already, 40 down to 49 follow
+        search = ChatSearch::new(false);
+        search.query = String::from("answer 4");
+        search.update(&chat_state.chat_messages);
+        assert_eq!(reveal_current(chat_state, &search, area), 37);
+        search.step(false);
+        assert_eq!(reveal_current(chat_state, &search, area), 4);
+        // `N` from the first hit wraps around to the last one, at the bottom
+        search.step(true);
+        search.step(true);
+        assert_eq!(search.reveal_target(), Some((49, 0)));
+        assert_eq!(reveal_current(chat_state, &search, area), 0);
+    }
+}
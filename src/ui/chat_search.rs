@@ -0,0 +1,186 @@
+use crate::ui::app_ui_state::ChatMessage;
+
+pub struct SearchHit {
+    pub message_idx: usize,
+    pub start: usize, // byte range inside the message
+    pub end: usize,
+}
+
+pub struct ChatSearch {
+    pub query: String,
+    pub backward: bool,
+    pub editing: bool,
+    pub hits: Vec<SearchHit>,
+    pub current: Option<usize>,
+}
+
+impl ChatSearch {
+    pub fn new(backward: bool) -> Self {
+        Self {
+            query: String::new(),
+            backward,
+            editing: true,
+            hits: Vec::new(),
+            current: None,
+        }
+    }
+
+    pub fn prompt_prefix(&self) -> char {
+        if self.backward { '?' } else { '/' }
+    }
+
+    /// Re-runs the query over the transcript and jumps to the first hit in search direction.
+    pub fn update(&mut self, messages: &[ChatMessage]) {
+        self.hits = find_hits(messages, &self.query);
+        self.current = if self.hits.is_empty() {
+            None
+        } else if self.backward {
+            Some(self.hits.len() - 1)
+        } else {
+            Some(0)
+        };
+    }
+
+    /// Moves to the next hit in search direction (or against it for `N`), wrapping around.
+    pub fn step(&mut self, reverse: bool) {
+        let len = self.hits.len();
+        let Some(current) = self.current else {
+            return;
+        };
+        if len == 0 {
+            return;
+        }
+
+        let towards_older = self.backward != reverse;
+        self.current = Some(if towards_older {
+            (current + len - 1) % len
+        } else {
+            (current + 1) % len
+        });
+    }
+
+    pub fn current_hit(&self) -> Option<&SearchHit> {
+        self.current.and_then(|idx| self.hits.get(idx))
+    }
+
+    /// Position to scroll into view, see `ChatState::reveal`.
+    pub fn reveal_target(&self) -> Option<(usize, usize)> {
+        self.current_hit().map(|hit| (hit.message_idx, hit.start))
+    }
+}
+
+// vim's smartcase: case sensitive only if the query has an upper case letter.
+// Folding is ascii only so byte offsets stay valid for the original message.
+fn find_hits(messages: &[ChatMessage], query: &str) -> Vec<SearchHit> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let case_sensitive = query.chars().any(char::is_uppercase);
+    let fold = |s: &str| {
+        if case_sensitive {
+            s.to_string()
+        } else {
+            s.to_ascii_lowercase()
+        }
+    };
+    let needle = fold(query);
+
+    let mut hits = Vec::new();
+    for (message_idx, msg) in messages.iter().enumerate() {
+        let haystack = fold(&msg.message);
+
+        let mut from = 0;
+        while let Some(pos) = haystack[from..].find(&needle) {
+            let start = from + pos;
+            let end = start + needle.len();
+            hits.push(SearchHit {
+                message_idx,
+                start,
+                end,
+            });
+            from = end;
+        }
+    }
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript(texts: &[&str]) -> Vec<ChatMessage> {
+        texts
+            .iter()
+            .map(|text| ChatMessage::prompt(text.to_string()))
+            .collect()
+    }
+
+    fn hits(messages: &[ChatMessage], query: &str) -> Vec<(usize, usize, usize)> {
+        find_hits(messages, query)
+            .into_iter()
+            .map(|hit| (hit.message_idx, hit.start, hit.end))
+            .collect()
+    }
+
+    #[test]
+    fn lower_case_queries_ignore_case() {
+        let messages = transcript(&["Error here", "no error", "ERROR"]);
+        assert_eq!(hits(&messages, "error"), [(0, 0, 5), (1, 3, 8), (2, 0, 5)]);
+        assert_eq!(hits(&messages, "Error"), [(0, 0, 5)]);
+        assert!(hits(&messages, "").is_empty());
+    }
+
+    #[test]
+    fn only_ascii_is_folded() {
+        // the offsets point into the original text, past the two byte Ä
+        let messages = transcript(&["Ärger ärger"]);
+        assert_eq!(hits(&messages, "ärger"), [(0, 7, 13)]);
+    }
+
+    #[test]
+    fn every_hit_in_a_line_is_found() {
+        let messages = transcript(&["abab ab", "aaaa"]);
+        assert_eq!(hits(&messages, "ab"), [(0, 0, 2), (0, 2, 4), (0, 5, 7)]);
+        // like vim, the next match starts after the last one
+        assert_eq!(hits(&messages, "aa"), [(1, 0, 2), (1, 2, 4)]);
+    }
+
+    #[test]
+    fn steps_wrap_around_in_search_direction() {
+        let messages = transcript(&["one", "two one", "one"]);
+        let mut search = ChatSearch::new(false);
+        search.query = String::from("one");
+        search.update(&messages);
+        assert_eq!(search.current, Some(0));
+        search.step(false);
+        search.step(false);
+        assert_eq!(search.reveal_target(), Some((2, 0)));
+        search.step(false);
+        assert_eq!(search.current, Some(0));
+        // `N` goes against the direction
+        search.step(true);
+        assert_eq!(search.current, Some(2));
+
+        // `?` starts at the newest hit and `n` goes back in time
+        let mut search = ChatSearch::new(true);
+        search.query = String::from("one");
+        search.update(&messages);
+        assert_eq!(search.current, Some(2));
+        search.step(false);
+        assert_eq!(search.reveal_target(), Some((1, 4)));
+        search.step(true);
+        search.step(true);
+        assert_eq!(search.current, Some(0));
+    }
+
+    #[test]
+    fn no_hits_leave_nothing_to_step_to() {
+        let mut search = ChatSearch::new(false);
+        search.query = String::from("missing");
+        search.update(&transcript(&["something else"]));
+        search.step(false);
+        assert!(search.current_hit().is_none());
+        assert_eq!(search.reveal_target(), None);
+    }
+}
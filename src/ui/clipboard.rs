@@ -0,0 +1,55 @@
+use std::io::{Write, stdout};
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Copies `text` to the system clipboard through the terminal (OSC 52), which also
+/// works over SSH and inside tmux with `set-clipboard on`.
+pub fn copy_to_clipboard(text: &str) {
+    let mut out = stdout();
+    let _ = write!(out, "\x1b]52;c;{}\x07", base64_encode(text.as_bytes()));
+    let _ = out.flush();
+}
+
+/// Contents of all fenced (```) code blocks, without the fences.
+pub fn extract_code_blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            match current.take() {
+                Some(lines) => blocks.push(lines.join("\n")),
+                None => current = Some(Vec::new()),
+            }
+        } else if let Some(lines) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    // an unterminated block (e.g. a response still streaming) counts as well
+    if let Some(lines) = current {
+        blocks.push(lines.join("\n"));
+    }
+    blocks
+}
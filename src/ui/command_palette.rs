@@ -0,0 +1,221 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use crate::ui::{
+    event_system::KeyOperationEvent,
+    fuzzy::fuzzy_filter,
+    key_bindings::{ACTIONS, KeyBindings},
+    list_view::{highlight_spans, scroll_into_view, wrap_step},
+    overlay::{Overlay, OverlayOutcome},
+    slash_commands::SLASH_COMMANDS,
+    theme::Theme,
+};
+
+enum PaletteAction {
+    Key(KeyOperationEvent),
+    Slash(&'static str), // fills in the prompt, most commands need arguments
+}
+
+struct PaletteEntry {
+    label: String, // what is searched and shown, the description followed by the action name
+    name_start: usize, // char index where the action name starts in `label`
+    keys: String,
+    action: PaletteAction,
+}
+
+impl PaletteEntry {
+    fn new(description: &str, name: &str, keys: String, action: PaletteAction) -> Self {
+        Self {
+            label: format!("{}  {}", description, name),
+            name_start: description.chars().count() + 2,
+            keys,
+            action,
+        }
+    }
+}
+
+pub struct CommandPalette {
+    entries: Vec<PaletteEntry>,
+    query: String,
+    matches: Vec<(usize, Vec<usize>)>, // (entry, matched char indices), best first
+    selected: usize,
+    scroll: usize,
+}
+
+const PALETTE_WIDTH: u16 = 72;
+const PALETTE_HEIGHT: u16 = 16;
+const KEYS_COLUMN_WIDTH: u16 = 16;
+
+impl CommandPalette {
+    /// Every action with its current keys, so the palette reflects config overrides,
+    /// followed by the slash commands.
+    pub fn new(key_bindings: &KeyBindings) -> Self {
+        let actions = ACTIONS
+            .iter()
+            .filter(|(_, event, _)| *event != KeyOperationEvent::Noop)
+            .map(|(name, event, description)| {
+                let keys = key_bindings.keys_for(*event);
+                PaletteEntry::new(
+                    description,
+                    name,
+                    keys.into_iter().take(2).collect::<Vec<_>>().join(", "),
+                    PaletteAction::Key(*event),
+                )
+            });
+        let slash_commands = SLASH_COMMANDS.iter().map(|command| {
+            PaletteEntry::new(
+                command.help,
+                command.usage,
+                String::new(),
+                PaletteAction::Slash(command.name),
+            )
+        });
+        let entries = actions.chain(slash_commands).collect();
+        let mut palette = Self {
+            entries,
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            scroll: 0,
+        };
+        palette.refilter();
+        palette
+    }
+
+    fn refilter(&mut self) {
+        self.matches = fuzzy_filter(&self.query, self.entries.iter().map(|e| e.label.as_str()));
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    fn step(&mut self, backward: bool) {
+        self.selected = wrap_step(self.selected, self.matches.len(), backward);
+    }
+
+    // matched characters in accent, the action name dimmed
+    fn entry_line(entry: &PaletteEntry, positions: &[usize], theme: &Theme) -> Line<'static> {
+        let mut spans = highlight_spans(&entry.label, positions, theme);
+        for span in spans.iter_mut().skip(entry.name_start) {
+            span.style = Style::default().fg(theme.dim).patch(span.style);
+        }
+        Line::from(spans)
+    }
+}
+
+impl Overlay for CommandPalette {
+    // top third, like most editors, so the list grows downwards
+    fn area_rect(&self, frame: Rect) -> Rect {
+        let width = PALETTE_WIDTH.min(frame.width);
+        let height = PALETTE_HEIGHT.min(frame.height);
+        Rect {
+            x: frame.x + (frame.width - width) / 2,
+            y: frame.y + (frame.height - height) / 4,
+            width,
+            height,
+        }
+    }
+
+    fn render(&mut self, area: Rect, buf: &mut Buffer, theme: &Theme) {
+        let block = Block::default()
+            .title(" Commands ")
+            .title_bottom(
+                Line::from(format!(" {}/{} ", self.matches.len(), self.entries.len()))
+                    .right_aligned(),
+            )
+            .borders(Borders::ALL)
+            .border_set(theme.glyphs.border)
+            .border_style(Style::default().fg(theme.accent))
+            .style(Style::default().bg(theme.surface));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [prompt_area, list_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(inner);
+        Paragraph::new(Line::from(vec![
+            Span::styled(": ", Style::default().fg(theme.accent)),
+            Span::raw(self.query.as_str()),
+            Span::styled("_", Style::default().fg(theme.dim)),
+        ]))
+        .render(prompt_area, buf);
+
+        let height = list_area.height as usize;
+        self.scroll = scroll_into_view(self.scroll, self.selected, height);
+
+        for (row, (match_idx, (entry_idx, positions))) in self
+            .matches
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(height)
+            .enumerate()
+        {
+            let entry = &self.entries[*entry_idx];
+            let selected = match_idx == self.selected;
+            let row_area = Rect {
+                y: list_area.y + row as u16,
+                height: 1,
+                ..list_area
+            };
+            let [label_area, keys_area] =
+                Layout::horizontal([Constraint::Fill(1), Constraint::Length(KEYS_COLUMN_WIDTH)])
+                    .areas(row_area);
+
+            let style = if selected {
+                theme.selection_style()
+            } else {
+                Style::default()
+            };
+            Paragraph::new(CommandPalette::entry_line(entry, positions, theme))
+                .style(style)
+                .render(label_area, buf);
+            Paragraph::new(Line::from(entry.keys.as_str()).right_aligned())
+                .style(style.add_modifier(Modifier::BOLD))
+                .render(keys_area, buf);
+        }
+    }
+
+    // only the first line, the query is a single line
+    fn handle_paste(&mut self, text: &str) {
+        self.query.push_str(text.lines().next().unwrap_or_default());
+        self.refilter();
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> OverlayOutcome {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => {
+                return match self.matches.get(self.selected) {
+                    Some((entry_idx, _)) => match self.entries[*entry_idx].action {
+                        PaletteAction::Key(event) => OverlayOutcome::CloseWith(event),
+                        PaletteAction::Slash(name) => {
+                            OverlayOutcome::CloseWithInput(format!("/{} ", name))
+                        }
+                    },
+                    None => OverlayOutcome::Consumed,
+                };
+            }
+            KeyCode::Up | KeyCode::BackTab => self.step(true),
+            KeyCode::Down | KeyCode::Tab => self.step(false),
+            KeyCode::Char('p' | 'k') if ctrl => self.step(true),
+            KeyCode::Char('n' | 'j') if ctrl => self.step(false),
+            KeyCode::Backspace => {
+                if self.query.pop().is_none() {
+                    return OverlayOutcome::Close;
+                }
+                self.refilter();
+            }
+            KeyCode::Char(c) if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) => {
+                self.query.push(c);
+                self.refilter();
+            }
+            _ => {}
+        }
+        OverlayOutcome::Consumed
+    }
+}
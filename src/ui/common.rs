@@ -1,4 +0,0 @@
-use ratatui::style::Color;
-
-pub const ACCENT_COLOR: Color = Color::Rgb(100, 160, 220);
-pub const SEMI_ACCENT_COLOR: Color = Color::Rgb(90, 120, 150);
@@ -0,0 +1,278 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::ui::{
+    app_ui_state::{AppUIState, ContextSource, Focus},
+    fuzzy::fuzzy_filter,
+    list_view::{highlight_spans, scroll_into_view, wrap_step},
+    nask_center_input::clamp_input_scroll,
+    renderable_trait::Renderable,
+    slash_commands::SLASH_COMMANDS,
+};
+
+const POPUP_ROWS: u16 = 8;
+const POPUP_WIDTH: u16 = 60;
+const DETAIL_COLUMN_WIDTH: u16 = 22;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    File,    // `@path` anywhere in the prompt
+    Command, // `/name` at the start of the prompt
+}
+
+struct CompletionItem {
+    label: String,
+    detail: String,
+    replacement: String, // what replaces the trigger and the query
+}
+
+/// The popup shown while typing an `@` mention or a slash command.
+pub struct Completion {
+    kind: CompletionKind,
+    start: usize, // char index of the `@` or `/`
+    query: String,
+    items: Vec<CompletionItem>,
+    matches: Vec<(usize, Vec<usize>)>, // (item, matched char indices), best first
+    selected: usize,
+    scroll: usize,
+}
+
+impl Completion {
+    fn new(kind: CompletionKind, start: usize, state: &mut AppUIState) -> Self {
+        let items = match kind {
+            CompletionKind::File => file_items(state),
+            CompletionKind::Command => SLASH_COMMANDS
+                .iter()
+                .map(|command| CompletionItem {
+                    label: format!("/{}", command.name),
+                    detail: command.usage.to_string(),
+                    replacement: format!("/{} ", command.name),
+                })
+                .collect(),
+        };
+        Self {
+            kind,
+            start,
+            query: String::new(),
+            items,
+            matches: Vec::new(),
+            selected: 0,
+            scroll: 0,
+        }
+    }
+
+    fn refilter(&mut self, query: &str) {
+        self.query = query.to_string();
+        self.matches = fuzzy_filter(query, self.items.iter().map(|i| i.label.as_str()));
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    pub fn has_matches(&self) -> bool {
+        !self.matches.is_empty()
+    }
+
+    pub fn step(&mut self, backward: bool) {
+        self.selected = wrap_step(self.selected, self.matches.len(), backward);
+    }
+}
+
+// open Neovim buffers first, they are what the user is most likely talking about
+fn file_items(state: &mut AppUIState) -> Vec<CompletionItem> {
+    let files = state.project_files();
+    let buffers: Vec<&str> = state
+        .additional_context_state
+        .entries
+        .iter()
+        .filter(|e| e.source == ContextSource::Nvim)
+        .map(|e| e.entry.as_str())
+        .collect();
+    let buffer_items = buffers.iter().map(|path| (*path, "nvim"));
+    let file_items = files
+        .iter()
+        .map(String::as_str)
+        .filter(|path| !buffers.contains(path))
+        .map(|path| (path, ""));
+    buffer_items
+        .chain(file_items)
+        .map(|(path, detail)| CompletionItem {
+            label: path.to_string(),
+            detail: detail.to_string(),
+            replacement: format!("@{} ", path),
+        })
+        .collect()
+}
+
+// the trigger and query in front of the cursor, if it is one
+fn trigger_before_cursor(value: &str, cursor: usize) -> Option<(CompletionKind, usize, String)> {
+    let chars: Vec<char> = value.chars().collect();
+    let start = chars[..cursor]
+        .iter()
+        .rposition(|c| c.is_whitespace())
+        .map(|idx| idx + 1)
+        .unwrap_or(0);
+    let query: String = chars[start..cursor].iter().skip(1).collect();
+    if start == cursor {
+        return None;
+    }
+    match chars[start] {
+        '@' => Some((CompletionKind::File, start, query)),
+        // `//` sends a literal slash, see submit_input
+        '/' if start == 0 && chars.get(1) != Some(&'/') => {
+            Some((CompletionKind::Command, start, query))
+        }
+        _ => None,
+    }
+}
+
+/// Opens, narrows or closes the popup after the prompt changed.
+pub fn refresh_completion(state: &mut AppUIState) {
+    let input = &state.input_box_state.input;
+    let Some((kind, start, query)) = trigger_before_cursor(input.value(), input.cursor()) else {
+        state.input_box_state.completion = None;
+        return;
+    };
+
+    let reuse = state
+        .input_box_state
+        .completion
+        .as_ref()
+        .is_some_and(|c| c.kind == kind && c.start == start);
+    if !reuse {
+        state.input_box_state.completion = Some(Completion::new(kind, start, state));
+    }
+    if let Some(completion) = state.input_box_state.completion.as_mut()
+        && (!reuse || completion.query != query)
+    {
+        completion.refilter(&query);
+    }
+}
+
+/// Replaces the trigger and query with the selected item.
+pub fn accept_completion(state: &mut AppUIState) {
+    let input_box_state = &mut state.input_box_state;
+    let Some(completion) = input_box_state.completion.take() else {
+        return;
+    };
+    let Some((item_idx, _)) = completion.matches.get(completion.selected) else {
+        return;
+    };
+    let replacement = &completion.items[*item_idx].replacement;
+
+    let value = input_box_state.input.value();
+    let cursor = input_box_state.input.cursor();
+    let before: String = value.chars().take(completion.start).collect();
+    let after: String = value.chars().skip(cursor).collect();
+    let new_cursor = completion.start + replacement.chars().count();
+    input_box_state.input = std::mem::take(&mut input_box_state.input)
+        .with_value(format!("{}{}{}", before, replacement, after.trim_start()))
+        .with_cursor(new_cursor);
+    clamp_input_scroll(input_box_state);
+}
+
+/// Paths of the `@path` mentions in `prompt`, in order and without duplicates.
+pub fn mentioned_paths(prompt: &str) -> Vec<&str> {
+    let mut paths: Vec<&str> = Vec::new();
+    for path in prompt
+        .split_whitespace()
+        .filter_map(|word| word.strip_prefix('@'))
+        // a mention at the end of a sentence
+        .map(|path| path.trim_end_matches([',', '.', ';', ':', '?', '!', ')']))
+    {
+        if !path.is_empty() && !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+pub struct CompletionPopup;
+
+pub fn create_completion_popup() -> &'static dyn Renderable {
+    static POPUP: CompletionPopup = CompletionPopup;
+    &POPUP
+}
+
+impl Renderable for CompletionPopup {
+    // gets the input box area, the popup sits on top of it
+    fn area_rect(&self, area: Rect) -> Rect {
+        let height = (POPUP_ROWS + 2).min(area.y);
+        Rect {
+            x: area.x,
+            y: area.y - height,
+            width: POPUP_WIDTH.min(area.width),
+            height,
+        }
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, state: &mut AppUIState) {
+        let theme = &state.theme;
+        let Some(completion) = state.input_box_state.completion.as_mut() else {
+            return;
+        };
+        if state.focus != Focus::Input || !completion.has_matches() {
+            return;
+        }
+
+        // shrink to the matches, anchored to the input box below
+        let rows = (completion.matches.len() as u16).min(area.height.saturating_sub(2));
+        let area = Rect {
+            y: area.bottom() - (rows + 2).min(area.height),
+            height: (rows + 2).min(area.height),
+            ..area
+        };
+        let title = match completion.kind {
+            CompletionKind::File => " Files ",
+            CompletionKind::Command => " Commands ",
+        };
+        let block = Block::default()
+            .title(title)
+            .title_bottom(Line::from(format!(" {} ", completion.matches.len())).right_aligned())
+            .borders(Borders::ALL)
+            .border_set(theme.glyphs.border)
+            .border_style(Style::default().fg(theme.accent))
+            .style(Style::default().bg(theme.surface));
+        let inner = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let height = inner.height as usize;
+        completion.scroll = scroll_into_view(completion.scroll, completion.selected, height);
+
+        for (row, (match_idx, (item_idx, positions))) in completion
+            .matches
+            .iter()
+            .enumerate()
+            .skip(completion.scroll)
+            .take(height)
+            .enumerate()
+        {
+            let item = &completion.items[*item_idx];
+            let row_area = Rect {
+                y: inner.y + row as u16,
+                height: 1,
+                ..inner
+            };
+            let [label_area, detail_area] =
+                Layout::horizontal([Constraint::Fill(1), Constraint::Length(DETAIL_COLUMN_WIDTH)])
+                    .areas(row_area);
+
+            let style = if match_idx == completion.selected {
+                theme.selection_style()
+            } else {
+                Style::default()
+            };
+            Paragraph::new(Line::from(highlight_spans(&item.label, positions, theme)))
+                .style(style)
+                .render(label_area, buf);
+            Paragraph::new(Line::from(item.detail.as_str()).right_aligned())
+                .style(style.fg(theme.dim))
+                .render(detail_area, buf);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, bail, eyre},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::app_config::state_dir,
+    ui::app_ui_state::{ChatMessage, ChatState},
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Role {
+    User,
+    Assistant,
+    Error,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedMessage {
+    role: Role,
+    text: String,
+}
+
+/// A transcript as stored in `<state dir>/conversations/<name>.toml`.
+#[derive(Serialize, Deserialize, Default)]
+pub struct SavedConversation {
+    system: Option<String>,
+    messages: Vec<SavedMessage>,
+}
+
+fn conversations_dir() -> Result<PathBuf> {
+    state_dir()
+        .map(|d| d.join("conversations"))
+        .ok_or_else(|| eyre!("no state directory, set HOME or XDG_STATE_HOME"))
+}
+
+fn conversation_path(name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        bail!("invalid conversation name `{}`", name);
+    }
+    Ok(conversations_dir()?.join(format!("{}.toml", name)))
+}
+
+pub fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl SavedConversation {
+    pub fn from_chat(chat_state: &ChatState) -> Self {
+        let messages = chat_state
+            .chat_messages
+            .iter()
+            .map(|msg| SavedMessage {
+                role: if msg.is_error {
+                    Role::Error
+                } else if msg.is_response {
+                    Role::Assistant
+                } else {
+                    Role::User
+                },
+                text: msg.message.clone(),
+            })
+            .collect();
+        Self {
+            system: chat_state.system_prompt.clone(),
+            messages,
+        }
+    }
+
+    /// Replaces the transcript, the view starts over at the newest message.
+    pub fn restore(self, chat_state: &mut ChatState) {
+        *chat_state = ChatState {
+            system_prompt: self.system,
+            ..ChatState::default()
+        };
+        chat_state.chat_messages = self
+            .messages
+            .into_iter()
+            .map(|saved| match saved.role {
+                Role::User => ChatMessage::prompt(saved.text),
+                Role::Error => ChatMessage::error(saved.text),
+                Role::Assistant => {
+                    let mut msg = ChatMessage::new(true, saved.text);
+                    msg.is_complete = true;
+                    msg
+                }
+            })
+            .collect();
+    }
+
+    pub fn save(&self, name: &str) -> Result<PathBuf> {
+        let path = conversation_path(name)?;
+        let raw = toml::to_string(self).wrap_err("serializing conversation")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).wrap_err_with(|| format!("creating {}", dir.display()))?;
+        }
+        fs::write(&path, raw).wrap_err_with(|| format!("writing {}", path.display()))?;
+        Ok(path)
+    }
+
+    pub fn load(name: &str) -> Result<Self> {
+        let path = conversation_path(name)?;
+        let raw = fs::read_to_string(&path).wrap_err_with(|| {
+            let saved = saved_names().unwrap_or_default();
+            if saved.is_empty() {
+                format!("no conversation `{}`, none saved yet", name)
+            } else {
+                format!("no conversation `{}`, saved: {}", name, saved.join(", "))
+            }
+        })?;
+        toml::from_str(&raw).wrap_err_with(|| format!("parsing {}", path.display()))
+    }
+
+    /// Markdown with one heading per message, errors are left out.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# nask conversation\n");
+        if let Some(system) = &self.system {
+            out.push_str(&format!("\n> {}\n", system.replace('\n', "\n> ")));
+        }
+        for msg in self.messages.iter().filter(|m| m.role != Role::Error) {
+            let heading = if msg.role == Role::User {
+                "You"
+            } else {
+                "Assistant"
+            };
+            out.push_str(&format!("\n## {}\n\n{}\n", heading, msg.text.trim_end()));
+        }
+        out
+    }
+}
+
+/// Saved conversation names, most recently saved first.
+pub fn saved_names() -> Result<Vec<String>> {
+    let dir = conversations_dir()?;
+    let Ok(read_dir) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut saved: Vec<(SystemTime, String)> = read_dir
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "toml" {
+                return None;
+            }
+            let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+            Some((modified, path.file_stem()?.to_string_lossy().into_owned()))
+        })
+        .collect();
+    saved.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    Ok(saved.into_iter().map(|(_, name)| name).collect())
+}
@@ -1,64 +1,289 @@
 use std::{collections::HashMap, sync::OnceLock};
 
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
-use tui_input::backend::crossterm::EventHandler;
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use ratatui::layout::Position;
+use tui_input::{Input, InputRequest};
+
+use color_eyre::Result;
 
 use crate::{
-    back_logic::message_loop::Command,
+    config::app_config::AppConfig,
     ui::{
-        app_ui_state::{AppUIState, InputMode},
-        nask_center_input::clamp_input_scroll,
+        app_ui_state::{AppUIState, CheckBoxEntry, ContextSource, Focus, InputMode},
+        chat::{NaskChat, scroll_chat},
+        command_palette::CommandPalette,
+        file_picker::FilePicker,
+        git_context::review_staged,
+        help_overlay::HelpOverlay,
+        key_bindings::{BindingScope, KeyBindings, KeyChord, KeyLookup},
+        nask_center_input::{
+            clamp_input_scroll, cursor_for_column, insert_text, update_history_search,
+        },
+        nvim_buffers::{create_nvim_buffers, entry_at_row},
+        overlay::{Confirm, OverlayOutcome, Toast},
+        renderable_trait::Renderable,
+        tmux_panes::open_tmux_picker,
+        vim_motions::{VimMotion, VimOperator, VimTextObject, put_text},
     },
 };
 
-#[derive(PartialEq, Eq)]
+const MOUSE_SCROLL_LINES: i32 = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum EventSignal {
     Continue,
+    EditInExternalEditor,
     Quit,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum KeyOperationEvent {
     ToggleBuffers,
-    SelectRightBuffer,
-    SelectLeftBuffer,
+    SelectNextBuffer,
+    SelectPrevBuffer,
     CheckSelectedBuffer,
+    ContextFilterStart,
+    ContextFilterConfirm,
+    ContextFilterCancel,
+    ContextFilterBackspace,
+    ForwardToContextFilter,
     InputSubmitted,
     InputChangeToInsertMode,
     InputChangeToNormalMode,
+    InputAppend,
+    InputAppendAtEnd,
+    InputInsertAtStart,
+    InputOpenLine,
+    VimMotion(VimMotion),
+    VimOperator(VimOperator),
+    VimTextObject(VimTextObject),
+    VimCount(u8),
+    VimDeleteChar,
+    VimPutAfter,
+    VimPutBefore,
+    VimCancel,
+    HistoryOlder,
+    HistoryNewer,
+    HistorySearchStart,
+    HistorySearchOlder,
+    HistorySearchAccept,
+    HistorySearchCancel,
+    HistorySearchBackspace,
+    ForwardToHistorySearch,
+    CompletionNext,
+    CompletionPrev,
+    CompletionAccept,
+    CompletionDismiss,
+    KeyPrefix,
+    OpenExternalEditor,
+    ChatScrollUp,
+    ChatScrollDown,
+    ChatSearchForward,
+    ChatSearchBackward,
+    ChatSearchNext,
+    ChatSearchPrev,
+    ChatSearchConfirm,
+    ChatSearchCancel,
+    ChatSearchBackspace,
+    ChatSearchClear,
+    ForwardToChatSearch,
+    FocusNext,
+    FocusPrev,
+    FocusChat,
+    FocusInput,
+    ShowHelp,
+    ShowCommandPalette,
+    ShowFilePicker,
+    ReviewStaged,
+    ShowTmuxPanes,
+    ChatSelectOlder,
+    ChatSelectNewer,
+    ChatSelectOldest,
+    ChatSelectNewest,
+    ChatYankMessage,
+    ChatYankCode,
+    ChatDeleteMessage,
+    ChatRegenerate,
+    ChatEditResend,
     Quit,
+    ForceQuit, // confirmed quit, not bindable
     ForwardToInput,
     Noop,
 }
 
+type KeyOperationGuard = fn(&AppUIState) -> bool;
+
+// digits in normal mode and after an operator are counts, `0` only once a count started
+fn get_count_event(key: KeyEvent, app_state: &AppUIState) -> Option<KeyOperationEvent> {
+    if key
+        .modifiers
+        .intersects(KeyModifiers::ALT | KeyModifiers::CONTROL)
+        || !app_state.pending_keys.is_empty()
+    {
+        return None;
+    }
+    match key.code {
+        KeyCode::Char('0') if app_state.input_box_state.vim.count.is_some() => {
+            Some(KeyOperationEvent::VimCount(0))
+        }
+        KeyCode::Char(c @ '1'..='9') => Some(KeyOperationEvent::VimCount(c as u8 - b'0')),
+        _ => None,
+    }
+}
+
+// keys typed while the reverse incremental history search (Ctrl-R) is active
+fn get_history_search_event(key: KeyEvent) -> KeyOperationEvent {
+    match (key.code, key.modifiers) {
+        (KeyCode::Char('r'), mods) if mods.contains(KeyModifiers::CONTROL) => {
+            KeyOperationEvent::HistorySearchOlder
+        }
+        (KeyCode::Char('g'), mods) if mods.contains(KeyModifiers::CONTROL) => {
+            KeyOperationEvent::HistorySearchCancel
+        }
+        (KeyCode::Esc, _) => KeyOperationEvent::HistorySearchCancel,
+        (KeyCode::Enter, _) => KeyOperationEvent::HistorySearchAccept,
+        (KeyCode::Backspace, _) => KeyOperationEvent::HistorySearchBackspace,
+        (KeyCode::Char(_), mods) if !mods.intersects(KeyModifiers::ALT | KeyModifiers::CONTROL) => {
+            KeyOperationEvent::ForwardToHistorySearch
+        }
+        _ => KeyOperationEvent::Noop,
+    }
+}
+
+// keys typed while the completion popup is open, everything else keeps editing the prompt
+fn get_completion_event(key: KeyEvent) -> Option<KeyOperationEvent> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Tab | KeyCode::Enter => Some(KeyOperationEvent::CompletionAccept),
+        KeyCode::Down => Some(KeyOperationEvent::CompletionNext),
+        KeyCode::Up | KeyCode::BackTab => Some(KeyOperationEvent::CompletionPrev),
+        KeyCode::Char('n') if ctrl => Some(KeyOperationEvent::CompletionNext),
+        KeyCode::Char('p') if ctrl => Some(KeyOperationEvent::CompletionPrev),
+        KeyCode::Esc => Some(KeyOperationEvent::CompletionDismiss),
+        _ => None,
+    }
+}
+
+// keys typed while the `/` or `?` chat search prompt is open
+fn get_chat_search_event(key: KeyEvent) -> KeyOperationEvent {
+    match (key.code, key.modifiers) {
+        (KeyCode::Esc, _) => KeyOperationEvent::ChatSearchCancel,
+        (KeyCode::Enter, _) => KeyOperationEvent::ChatSearchConfirm,
+        (KeyCode::Backspace, _) => KeyOperationEvent::ChatSearchBackspace,
+        (KeyCode::Char(_), mods) if !mods.intersects(KeyModifiers::ALT | KeyModifiers::CONTROL) => {
+            KeyOperationEvent::ForwardToChatSearch
+        }
+        _ => KeyOperationEvent::Noop,
+    }
+}
+
+// keys typed while the `/` filter of the context panel is open
+fn get_context_filter_event(key: KeyEvent) -> KeyOperationEvent {
+    match (key.code, key.modifiers) {
+        (KeyCode::Esc, _) => KeyOperationEvent::ContextFilterCancel,
+        (KeyCode::Enter, _) => KeyOperationEvent::ContextFilterConfirm,
+        (KeyCode::Backspace, _) => KeyOperationEvent::ContextFilterBackspace,
+        (KeyCode::Char(_), mods) if !mods.intersects(KeyModifiers::ALT | KeyModifiers::CONTROL) => {
+            KeyOperationEvent::ForwardToContextFilter
+        }
+        _ => KeyOperationEvent::Noop,
+    }
+}
+
+// keys without a binding: text for the prompt, nothing elsewhere
+fn get_unbound_event(app_state: &AppUIState) -> KeyOperationEvent {
+    if app_state.focus != Focus::Input {
+        return KeyOperationEvent::Noop;
+    }
+    // will be checked by the guard in get_key_operation_event and either
+    // get nooped or kept
+    KeyOperationEvent::ForwardToInput
+}
+
 fn get_key_operation_event(
     key: KeyEvent,
     app_state: &AppUIState,
-    guard_map: &HashMap<KeyOperationEvent, fn(&AppUIState) -> bool>,
+    key_bindings: &KeyBindings,
+    guard_map: &HashMap<KeyOperationEvent, KeyOperationGuard>,
 ) -> KeyOperationEvent {
-    let ev = match (key.code, key.modifiers, app_state.input_box_state.mode) {
-        (KeyCode::Char('i'), _, InputMode::Normal) => KeyOperationEvent::InputChangeToInsertMode,
-        (KeyCode::Char('q'), _, InputMode::Normal) => KeyOperationEvent::Quit,
-        (KeyCode::Esc, _, InputMode::Insert) => KeyOperationEvent::InputChangeToNormalMode,
+    let input_mode = app_state.input_box_state.mode;
 
-        (KeyCode::Enter, _, InputMode::Insert) => KeyOperationEvent::InputSubmitted,
+    if app_state.input_box_state.history_search.is_some() {
+        return get_history_search_event(key);
+    }
+    if app_state.focus == Focus::Input
+        && input_mode == InputMode::Insert
+        && app_state
+            .input_box_state
+            .completion
+            .as_ref()
+            .is_some_and(|completion| completion.has_matches())
+        && let Some(ev) = get_completion_event(key)
+    {
+        return ev;
+    }
+    if app_state
+        .chat_state
+        .search
+        .as_ref()
+        .is_some_and(|search| search.editing)
+    {
+        return get_chat_search_event(key);
+    }
+    if app_state.focus == Focus::Contexts
+        && app_state
+            .additional_context_state
+            .filter
+            .as_ref()
+            .is_some_and(|filter| filter.editing)
+    {
+        return get_context_filter_event(key);
+    }
+    let normal_input = app_state.focus == Focus::Input && input_mode == InputMode::Normal;
+    if normal_input && let Some(ev) = get_count_event(key, app_state) {
+        return ev;
+    }
 
-        (KeyCode::Char('x'), mods, _) if mods.contains(KeyModifiers::ALT) => {
-            KeyOperationEvent::ToggleBuffers
-        }
-        (KeyCode::Up, mods, _) if mods.contains(KeyModifiers::ALT) => {
-            KeyOperationEvent::CheckSelectedBuffer
-        }
-        (KeyCode::Left, mods, _) if mods.contains(KeyModifiers::ALT) => {
-            KeyOperationEvent::SelectLeftBuffer
+    let keys: Vec<KeyChord> = app_state
+        .pending_keys
+        .iter()
+        .chain([&key])
+        .map(KeyChord::from_event)
+        .collect();
+
+    // an operator waits for its motion, anything unbound cancels it like in vim
+    if normal_input && app_state.input_box_state.vim.is_pending() {
+        return match key_bindings.lookup(BindingScope::OPERATOR_PENDING, &keys) {
+            KeyLookup::Matched(ev) => ev,
+            KeyLookup::Prefix => KeyOperationEvent::KeyPrefix,
+            KeyLookup::NoMatch => KeyOperationEvent::VimCancel,
+        };
+    }
+
+    let scopes = BindingScope::lookup_order(app_state.focus, input_mode);
+    let ev = match key_bindings.lookup(scopes, &keys) {
+        KeyLookup::Matched(ev) => ev,
+        KeyLookup::Prefix => KeyOperationEvent::KeyPrefix,
+        KeyLookup::NoMatch if !app_state.pending_keys.is_empty() => KeyOperationEvent::Noop,
+        KeyLookup::NoMatch => get_unbound_event(app_state),
+    };
+
+    // Esc in normal mode first dismisses a finished chat search, in the
+    // context panel it drops the filter before leaving
+    let ev = match ev {
+        KeyOperationEvent::VimCancel if app_state.chat_state.search.is_some() => {
+            KeyOperationEvent::ChatSearchClear
         }
-        (KeyCode::Right, mods, _) if mods.contains(KeyModifiers::ALT) => {
-            KeyOperationEvent::SelectRightBuffer
+        KeyOperationEvent::FocusInput
+            if app_state.focus == Focus::Contexts
+                && app_state.additional_context_state.filter.is_some()
+                && key.code == KeyCode::Esc =>
+        {
+            KeyOperationEvent::ContextFilterCancel
         }
-
-        _ => KeyOperationEvent::ForwardToInput,
-        // will be checked by the guard below and either
-        // get nooped or kept
+        ev => ev,
     };
 
     match guard_map.get(&ev) {
@@ -68,31 +293,40 @@ fn get_key_operation_event(
     }
 }
 
-pub struct DedicatedEventProcessor;
+pub struct DedicatedEventProcessor {
+    paste_attach_threshold_lines: usize,
+    key_bindings: KeyBindings,
+    nask_chat: NaskChat, // the same components `render` draws
+}
 
 impl DedicatedEventProcessor {
-    fn get_key_operation_guard_map() -> &'static HashMap<KeyOperationEvent, fn(&AppUIState) -> bool>
-    {
-        static GUARD_MAP: OnceLock<HashMap<KeyOperationEvent, fn(&AppUIState) -> bool>> =
-            OnceLock::new();
-
-        GUARD_MAP.get_or_init(|| {
-            fn not_collapsed_guard(state: &AppUIState) -> bool {
-                !state.additional_context_state.collapsed
-            }
-
-            let mut map: HashMap<KeyOperationEvent, fn(&AppUIState) -> bool> = HashMap::new();
+    pub fn new(config: &AppConfig, nask_chat: NaskChat) -> Result<Self> {
+        Ok(Self {
+            paste_attach_threshold_lines: config.paste.attach_threshold_lines,
+            key_bindings: KeyBindings::new(&config.keys)?,
+            nask_chat,
+        })
+    }
 
-            map.insert(KeyOperationEvent::SelectLeftBuffer, not_collapsed_guard);
+    fn get_key_operation_guard_map() -> &'static HashMap<KeyOperationEvent, KeyOperationGuard> {
+        static GUARD_MAP: OnceLock<HashMap<KeyOperationEvent, KeyOperationGuard>> = OnceLock::new();
 
-            map.insert(KeyOperationEvent::SelectRightBuffer, not_collapsed_guard);
+        GUARD_MAP.get_or_init(|| {
+            let mut map: HashMap<KeyOperationEvent, KeyOperationGuard> = HashMap::new();
 
-            map.insert(KeyOperationEvent::CheckSelectedBuffer, not_collapsed_guard);
+            map.insert(KeyOperationEvent::FocusChat, |state| {
+                !state.chat_state.chat_messages.is_empty()
+            });
 
             map.insert(KeyOperationEvent::ForwardToInput, |state| {
                 state.input_box_state.mode == InputMode::Insert
             });
 
+            // the streaming answer would land after the truncated transcript
+            map.insert(KeyOperationEvent::ChatRegenerate, |state| {
+                !state.prompt_in_flight()
+            });
+
             map
         })
     }
@@ -101,93 +335,221 @@ impl DedicatedEventProcessor {
         let key_operation_event = get_key_operation_event(
             key,
             state,
+            &self.key_bindings,
             DedicatedEventProcessor::get_key_operation_guard_map(),
         );
+        if key_operation_event != KeyOperationEvent::KeyPrefix {
+            state.pending_keys.clear();
+        }
+        if key_operation_event == KeyOperationEvent::KeyPrefix {
+            state.pending_keys.push(key);
+            return EventSignal::Continue;
+        }
+        self.process_operation(key_operation_event, key, state)
+    }
+
+    fn process_operation(
+        &self,
+        key_operation_event: KeyOperationEvent,
+        key: KeyEvent,
+        state: &mut AppUIState,
+    ) -> EventSignal {
         match key_operation_event {
-            KeyOperationEvent::ToggleBuffers => {
-                state.additional_context_state.collapsed =
-                    !state.additional_context_state.collapsed;
+            // an answer still streaming in would be lost
+            KeyOperationEvent::Quit if state.generation.started.is_some() => {
+                state.overlays.push(Confirm::new(
+                    "An answer is still generating. Quit anyway?",
+                    KeyOperationEvent::ForceQuit,
+                ));
             }
-            KeyOperationEvent::CheckSelectedBuffer => {
-                if let Some(item) = state
-                    .additional_context_state
-                    .entries
-                    .iter_mut()
-                    .find(|v| v.selected)
-                {
-                    item.checked = !item.checked;
-                }
+            KeyOperationEvent::Quit | KeyOperationEvent::ForceQuit => return EventSignal::Quit,
+            KeyOperationEvent::OpenExternalEditor => return EventSignal::EditInExternalEditor,
+            KeyOperationEvent::FocusNext => state.cycle_focus(false),
+            KeyOperationEvent::FocusPrev => state.cycle_focus(true),
+            KeyOperationEvent::FocusChat => state.set_focus(Focus::Chat),
+            KeyOperationEvent::FocusInput => state.set_focus(Focus::Input),
+            // built from the live table, so the overlay shows config overrides too
+            KeyOperationEvent::ShowHelp => {
+                state
+                    .overlays
+                    .push(HelpOverlay::new(self.key_bindings.help_sections()));
             }
-            KeyOperationEvent::SelectLeftBuffer => {
-                if let Some((idx, item)) = state
-                    .additional_context_state
-                    .entries
-                    .iter_mut()
-                    .enumerate()
-                    .find(|(_idx, v)| v.selected)
-                {
-                    item.selected = false;
-                    let mut new_select_idx = idx;
-                    if new_select_idx == 0 {
-                        new_select_idx = state.additional_context_state.entries.len() - 1;
-                    } else {
-                        new_select_idx -= 1;
-                    }
-                    let new_select_item = state
-                        .additional_context_state
-                        .entries
-                        .get_mut(new_select_idx);
-                    if let Some(new_item) = new_select_item {
-                        new_item.selected = true;
-                    }
-                }
+            KeyOperationEvent::ShowCommandPalette => {
+                state.overlays.push(CommandPalette::new(&self.key_bindings));
             }
-            KeyOperationEvent::SelectRightBuffer => {
-                if let Some((idx, item)) = state
-                    .additional_context_state
-                    .entries
-                    .iter_mut()
-                    .enumerate()
-                    .find(|(_idx, v)| v.selected)
-                {
-                    item.selected = false;
-                    let mut new_select_idx = idx;
-                    if new_select_idx == state.additional_context_state.entries.len() - 1 {
-                        new_select_idx = 0;
-                    } else {
-                        new_select_idx += 1;
-                    }
-                    let new_select_item = state
-                        .additional_context_state
-                        .entries
-                        .get_mut(new_select_idx);
-                    if let Some(new_item) = new_select_item {
-                        new_item.selected = true;
-                    }
-                }
+            KeyOperationEvent::ShowFilePicker => {
+                let files = state.project_files();
+                state.overlays.push(FilePicker::new(files));
             }
-            KeyOperationEvent::InputChangeToInsertMode => {
-                state.input_box_state.mode = InputMode::Insert
+            KeyOperationEvent::ShowTmuxPanes => open_tmux_picker(state),
+            KeyOperationEvent::ReviewStaged => review_staged(state),
+            KeyOperationEvent::Noop => {}
+            _ => return self.route_to_components(key_operation_event, key, state),
+        }
+        EventSignal::Continue
+    }
+
+    // the focused pane gets the first go, the others pick up what they own
+    // (e.g. `n` in the input still steps through the chat search)
+    fn route_to_components(
+        &self,
+        key_operation_event: KeyOperationEvent,
+        key: KeyEvent,
+        state: &mut AppUIState,
+    ) -> EventSignal {
+        let components: [(Focus, &dyn Renderable); 3] = [
+            (Focus::Input, self.nask_chat.input_box),
+            (Focus::Chat, self.nask_chat.chat_dialog),
+            (Focus::Contexts, create_nvim_buffers()),
+        ];
+
+        let focus = state.focus;
+        let focused = components.iter().filter(|(f, _)| *f == focus);
+        let others = components.iter().filter(|(f, _)| *f != focus);
+        for (_, component) in focused.chain(others) {
+            if let Some(signal) = component.handle_key_operation(key_operation_event, key, state) {
+                return signal;
             }
-            KeyOperationEvent::InputChangeToNormalMode => {
-                state.input_box_state.mode = InputMode::Normal
+        }
+        EventSignal::Continue
+    }
+
+    fn process_overlay_key(&self, key: KeyEvent, state: &mut AppUIState) -> EventSignal {
+        match state.overlays.handle_key(key) {
+            // same guards as for a key press, e.g. no chat focus without messages
+            Some(OverlayOutcome::CloseWith(ev)) => {
+                let guard_map = DedicatedEventProcessor::get_key_operation_guard_map();
+                if guard_map.get(&ev).is_some_and(|guard| !guard(state)) {
+                    return EventSignal::Continue;
+                }
+                self.process_operation(ev, key, state)
             }
-            KeyOperationEvent::Quit => {
-                return EventSignal::Quit;
+            Some(OverlayOutcome::CloseWithInput(text)) => {
+                let input_box_state = &mut state.input_box_state;
+                input_box_state.input = Input::new(text);
+                input_box_state.mode = InputMode::Insert;
+                input_box_state.vim.reset();
+                clamp_input_scroll(input_box_state);
+                state.set_focus(Focus::Input);
+                EventSignal::Continue
             }
-            KeyOperationEvent::InputSubmitted => {
-                (state.pump_message_loop)(Command::ChatMessage(String::from(
-                    state.input_box_state.input.value(),
-                )));
-                state.input_box_state.input.reset();
-                clamp_input_scroll(&mut state.input_box_state);
+            Some(OverlayOutcome::CloseWithContexts(entries)) => {
+                let message = match entries.len() {
+                    0 => String::from("nothing added, not a text file"),
+                    1 => format!("added {}", entries[0].entry),
+                    n => format!("added {} files", n),
+                };
+                for entry in entries {
+                    state.additional_context_state.attach(entry);
+                }
+                state.overlays.push(Toast::new(message));
+                EventSignal::Continue
             }
-            KeyOperationEvent::ForwardToInput => {
-                EventHandler::handle_event(&mut state.input_box_state.input, &Event::Key(key));
-                clamp_input_scroll(&mut state.input_box_state);
+            Some(OverlayOutcome::CloseWithCommand(command)) => {
+                (state.pump_message_loop)(command);
+                EventSignal::Continue
             }
+            Some(_) => EventSignal::Continue,
+            None => self.process_key_event(key, state),
+        }
+    }
 
-            KeyOperationEvent::Noop => {}
+    fn process_mouse_event(&self, mouse: MouseEvent, state: &mut AppUIState) -> EventSignal {
+        let position = Position::new(mouse.column, mouse.row);
+
+        match mouse.kind {
+            MouseEventKind::ScrollUp => scroll_chat(state, MOUSE_SCROLL_LINES),
+            MouseEventKind::ScrollDown => scroll_chat(state, -MOUSE_SCROLL_LINES),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let buffers_rect = state.layout.contexts;
+                let input_rect = state.layout.input;
+
+                if buffers_rect.contains(position) {
+                    let was_collapsed = state.additional_context_state.collapsed;
+                    state.set_focus(Focus::Contexts);
+                    let context_state = &mut state.additional_context_state;
+                    // the first click only expands the panel
+                    if !was_collapsed
+                        && let Some(idx) = entry_at_row(context_state, buffers_rect, mouse.row)
+                    {
+                        context_state.select(idx);
+                        context_state.entries[idx].checked = !context_state.entries[idx].checked;
+                    }
+                } else if input_rect.contains(position) {
+                    state.set_focus(Focus::Input);
+                    let input_box_state = &mut state.input_box_state;
+                    let cursor = cursor_for_column(input_box_state, input_rect, mouse.column);
+                    input_box_state
+                        .input
+                        .handle(InputRequest::SetCursor(cursor));
+                    clamp_input_scroll(input_box_state);
+                }
+            }
+            _ => {}
+        }
+        EventSignal::Continue
+    }
+
+    fn process_paste_event(&self, text: String, state: &mut AppUIState) -> EventSignal {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        // queries are single line, they only take the first one
+        let first_line = text.lines().next().unwrap_or_default();
+
+        // the paste goes where the keys would go
+        if state.overlays.handle_paste(&text) {
+            return EventSignal::Continue;
+        }
+        if let Some(search) = state.input_box_state.history_search.as_mut() {
+            search.query.push_str(first_line);
+            update_history_search(&mut state.input_box_state, false);
+            return EventSignal::Continue;
+        }
+        let chat_state = &mut state.chat_state;
+        if let Some(search) = chat_state.search.as_mut().filter(|search| search.editing) {
+            search.query.push_str(first_line);
+            search.update(&chat_state.chat_messages);
+            chat_state.reveal = search.reveal_target();
+            return EventSignal::Continue;
+        }
+        let context_state = &mut state.additional_context_state;
+        if state.focus == Focus::Contexts
+            && let Some(filter) = context_state
+                .filter
+                .as_mut()
+                .filter(|filter| filter.editing)
+        {
+            filter.query.push_str(first_line);
+            context_state.refilter();
+            return EventSignal::Continue;
+        }
+        if state.focus != Focus::Input {
+            return EventSignal::Continue;
+        }
+
+        let line_count = text.lines().count();
+        let threshold = self.paste_attach_threshold_lines;
+        if threshold > 0 && line_count > threshold {
+            state.additional_context_state.push_entry(CheckBoxEntry {
+                checked: true,
+                selected: false,
+                entry: format!("pasted {} lines", line_count),
+                content: Some(text),
+                source: ContextSource::Paste,
+            });
+            return EventSignal::Continue;
+        }
+
+        // the prompt is a single line
+        let text = text.lines().collect::<Vec<_>>().join(" ");
+        let input_box_state = &mut state.input_box_state;
+        match input_box_state.mode {
+            InputMode::Insert => insert_text(input_box_state, &text),
+            // like `p` with the pasted text as the register
+            InputMode::Normal => {
+                input_box_state.vim.reset();
+                put_text(input_box_state, &text, false);
+                clamp_input_scroll(input_box_state);
+            }
         }
         EventSignal::Continue
     }
@@ -199,9 +561,68 @@ pub trait EventProcessor {
 
 impl EventProcessor for Event {
     fn process(self, state: &mut AppUIState, processor: &DedicatedEventProcessor) -> EventSignal {
+        // modal overlays capture the input before anything below them
+        if state.overlays.has_modal() {
+            return match self {
+                Event::Key(key_event) => processor.process_overlay_key(key_event, state),
+                Event::Paste(text) => processor.process_paste_event(text, state),
+                _ => EventSignal::Continue,
+            };
+        }
         match self {
             Event::Key(key_event) => processor.process_key_event(key_event, state),
+            Event::Paste(text) => processor.process_paste_event(text, state),
+            Event::Mouse(mouse_event) => processor.process_mouse_event(mouse_event, state),
             _ => EventSignal::Continue, // Handle other cases if necessary
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::chat::create_nask_chat;
+
+    fn paste(state: &mut AppUIState, text: &str) {
+        let processor = DedicatedEventProcessor::new(&AppConfig::default(), create_nask_chat())
+            .expect("default bindings conflict");
+        Event::Paste(text.to_string()).process(state, &processor);
+    }
+
+    fn input(value: &str, cursor: usize, mode: InputMode) -> AppUIState {
+        let mut state = AppUIState::new(|_| {});
+        state.input_box_state.input = Input::new(value.to_string()).with_cursor(cursor);
+        state.input_box_state.mode = mode;
+        state
+    }
+
+    #[test]
+    fn insert_mode_paste_joins_lines() {
+        let mut state = input("ab", 1, InputMode::Insert);
+        paste(&mut state, "one\r\ntwo\n");
+        assert_eq!(state.input_box_state.input.value(), "aone twob");
+        assert_eq!(state.input_box_state.input.cursor(), 8);
+    }
+
+    #[test]
+    fn normal_mode_paste_puts_after_the_cursor() {
+        let mut state = input("ab", 0, InputMode::Normal);
+        state.input_box_state.vim.push_count(3);
+        paste(&mut state, "xy");
+        assert!(state.input_box_state.mode == InputMode::Normal);
+        assert_eq!(state.input_box_state.input.value(), "axyb");
+        assert_eq!(state.input_box_state.input.cursor(), 2);
+        assert_eq!(state.input_box_state.vim.count, None);
+    }
+
+    #[test]
+    fn long_paste_becomes_a_context() {
+        let mut state = input("", 0, InputMode::Insert);
+        let text = "line\n".repeat(41);
+        paste(&mut state, &text);
+        assert_eq!(state.input_box_state.input.value(), "");
+        let entry = state.additional_context_state.entries.last().unwrap();
+        assert_eq!(entry.entry, "pasted 41 lines");
+        assert_eq!(entry.content.as_deref(), Some(text.as_str()));
+    }
+}
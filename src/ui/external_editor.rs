@@ -0,0 +1,40 @@
+use std::{env, fs, io::Write, process::Command};
+
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, eyre},
+};
+
+fn editor_command() -> Vec<String> {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .map(|cmd| cmd.split_whitespace().map(String::from).collect::<Vec<_>>())
+        .find(|parts| !parts.is_empty())
+        .unwrap_or_else(|| vec![String::from("vi")])
+}
+
+/// Opens `$VISUAL`/`$EDITOR` on a temp file seeded with `initial` and returns
+/// the edited text. The caller is responsible for suspending the terminal.
+pub fn edit_in_external_editor(initial: &str) -> Result<String> {
+    let mut file = tempfile::Builder::new()
+        .prefix("nask-prompt-")
+        .suffix(".md")
+        .tempfile()
+        .wrap_err("creating prompt file")?;
+    file.write_all(initial.as_bytes())
+        .wrap_err("writing prompt file")?;
+
+    let command = editor_command();
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .arg(file.path())
+        .status()
+        .wrap_err_with(|| format!("launching editor `{}`", command[0]))?;
+    if !status.success() {
+        return Err(eyre!("editor `{}` exited with {}", command[0], status));
+    }
+
+    let edited = fs::read_to_string(file.path()).wrap_err("reading prompt file")?;
+    Ok(edited.trim_end_matches(['\n', '\r']).to_string())
+}
@@ -0,0 +1,280 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use crate::{
+    back_logic::project_files::{read_attachable, read_head},
+    ui::{
+        app_ui_state::{CheckBoxEntry, ContextSource},
+        fuzzy::fuzzy_filter,
+        list_view::{highlight_spans, scroll_into_view, wrap_step},
+        overlay::{Overlay, OverlayOutcome, centered_rect},
+        theme::Theme,
+    },
+};
+
+const PICKER_MARGIN: u16 = 4;
+const PREVIEW_LINES: usize = 200;
+// below this the preview is dropped and the list gets the whole width
+const PREVIEW_MIN_WIDTH: u16 = 80;
+
+/// Fuzzy finder over the project files, Tab marks several, Enter adds them as contexts.
+pub struct FilePicker {
+    files: Arc<[String]>, // as walked when the picker opened
+    query: String,
+    matches: Vec<(usize, Vec<usize>)>, // (file, matched char indices), best first
+    selected: usize,
+    scroll: usize,
+    marked: BTreeSet<usize>,
+    preview: Option<(usize, Vec<String>)>, // file index and its first lines
+    error: Option<String>,                 // why the last Enter attached nothing
+}
+
+impl FilePicker {
+    pub fn new(files: Arc<[String]>) -> Self {
+        let mut picker = Self {
+            files,
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            scroll: 0,
+            marked: BTreeSet::new(),
+            preview: None,
+            error: None,
+        };
+        picker.refilter();
+        picker
+    }
+
+    fn refilter(&mut self) {
+        self.matches = fuzzy_filter(&self.query, self.files.iter().map(String::as_str));
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    fn step(&mut self, backward: bool) {
+        self.selected = wrap_step(self.selected, self.matches.len(), backward);
+    }
+
+    fn highlighted(&self) -> Option<usize> {
+        self.matches.get(self.selected).map(|(idx, _)| *idx)
+    }
+
+    fn toggle_mark(&mut self) {
+        if let Some(idx) = self.highlighted()
+            && !self.marked.remove(&idx)
+        {
+            self.marked.insert(idx);
+        }
+    }
+
+    // the marked files, or the highlighted one when nothing is marked;
+    // fails on the first file that is too large or not text
+    fn chosen_entries(&self) -> color_eyre::Result<Vec<CheckBoxEntry>> {
+        let chosen: Vec<usize> = if self.marked.is_empty() {
+            self.highlighted().into_iter().collect()
+        } else {
+            self.marked.iter().copied().collect()
+        };
+        chosen
+            .into_iter()
+            .map(|idx| {
+                let path = &self.files[idx];
+                Ok(CheckBoxEntry {
+                    checked: true,
+                    selected: false,
+                    entry: path.clone(),
+                    content: Some(read_attachable(path)?),
+                    source: ContextSource::File,
+                })
+            })
+            .collect()
+    }
+
+    fn preview_lines(&mut self) -> &[String] {
+        let Some(idx) = self.highlighted() else {
+            return &[];
+        };
+        if self
+            .preview
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != idx)
+        {
+            let lines = match read_head(&self.files[idx]) {
+                Some(content) => content
+                    .lines()
+                    .take(PREVIEW_LINES)
+                    .map(|line| line.replace('\t', "    "))
+                    .collect(),
+                None => vec![String::from("(no preview, not a text file)")],
+            };
+            self.preview = Some((idx, lines));
+        }
+        self.preview
+            .as_ref()
+            .map(|(_, lines)| lines.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn render_list(&mut self, area: Rect, buf: &mut Buffer, theme: &Theme) {
+        let height = area.height as usize;
+        self.scroll = scroll_into_view(self.scroll, self.selected, height);
+
+        for (row, (match_idx, (file_idx, positions))) in self
+            .matches
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(height)
+            .enumerate()
+        {
+            let marker = if self.marked.contains(file_idx) {
+                Span::styled("+ ", Style::default().fg(theme.accent))
+            } else {
+                Span::raw("  ")
+            };
+            let mut spans = vec![marker];
+            spans.extend(highlight_spans(&self.files[*file_idx], positions, theme));
+
+            let style = if match_idx == self.selected {
+                theme.selection_style()
+            } else {
+                Style::default()
+            };
+            let row_area = Rect {
+                y: area.y + row as u16,
+                height: 1,
+                ..area
+            };
+            Paragraph::new(Line::from(spans))
+                .style(style)
+                .render(row_area, buf);
+        }
+    }
+}
+
+impl Overlay for FilePicker {
+    fn area_rect(&self, frame: Rect) -> Rect {
+        centered_rect(
+            frame,
+            frame.width.saturating_sub(PICKER_MARGIN * 2),
+            frame.height.saturating_sub(PICKER_MARGIN),
+        )
+    }
+
+    fn render(&mut self, area: Rect, buf: &mut Buffer, theme: &Theme) {
+        let status = if self.marked.is_empty() {
+            format!(" {}/{} ", self.matches.len(), self.files.len())
+        } else {
+            format!(
+                " {} marked, {}/{} ",
+                self.marked.len(),
+                self.matches.len(),
+                self.files.len()
+            )
+        };
+        let hint = match &self.error {
+            Some(error) => {
+                Line::from(format!(" {} ", error)).style(Style::default().fg(theme.error))
+            }
+            None => Line::from(" Tab mark  Enter add ").style(Style::default().fg(theme.dim)),
+        };
+        let block = Block::default()
+            .title(" Add files ")
+            .title_bottom(hint)
+            .title_bottom(Line::from(status).right_aligned())
+            .borders(Borders::ALL)
+            .border_set(theme.glyphs.border)
+            .border_style(Style::default().fg(theme.accent))
+            .style(Style::default().bg(theme.surface));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [prompt_area, body_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(inner);
+        Paragraph::new(Line::from(vec![
+            Span::styled("> ", Style::default().fg(theme.accent)),
+            Span::raw(self.query.as_str()),
+            Span::styled("_", Style::default().fg(theme.dim)),
+        ]))
+        .render(prompt_area, buf);
+
+        if body_area.width < PREVIEW_MIN_WIDTH {
+            self.render_list(body_area, buf, theme);
+            return;
+        }
+        let [list_area, preview_area] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Fill(1)]).areas(body_area);
+        self.render_list(list_area, buf, theme);
+
+        let preview_block = Block::default()
+            .borders(Borders::LEFT)
+            .border_set(theme.glyphs.border)
+            .border_style(Style::default().fg(theme.border));
+        let preview_inner = preview_block.inner(preview_area);
+        preview_block.render(preview_area, buf);
+        let lines: Vec<Line> = self
+            .preview_lines()
+            .iter()
+            .take(preview_inner.height as usize)
+            .map(|line| Line::from(line.as_str()))
+            .collect();
+        Paragraph::new(lines)
+            .style(Style::default().fg(theme.dim))
+            .render(preview_inner, buf);
+    }
+
+    // only the first line, the query is a single line
+    fn handle_paste(&mut self, text: &str) {
+        self.error = None;
+        self.query.push_str(text.lines().next().unwrap_or_default());
+        self.refilter();
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> OverlayOutcome {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        self.error = None;
+        match key.code {
+            KeyCode::Enter => {
+                if self.matches.is_empty() {
+                    return OverlayOutcome::Consumed;
+                }
+                match self.chosen_entries() {
+                    Ok(entries) => return OverlayOutcome::CloseWithContexts(entries),
+                    Err(e) => self.error = Some(format!("{:#}", e)),
+                }
+            }
+            KeyCode::Tab => {
+                self.toggle_mark();
+                self.step(false);
+            }
+            KeyCode::BackTab => {
+                self.toggle_mark();
+                self.step(true);
+            }
+            KeyCode::Up => self.step(true),
+            KeyCode::Down => self.step(false),
+            KeyCode::Char('p' | 'k') if ctrl => self.step(true),
+            KeyCode::Char('n' | 'j') if ctrl => self.step(false),
+            KeyCode::Backspace => {
+                if self.query.pop().is_none() {
+                    return OverlayOutcome::Close;
+                }
+                self.refilter();
+            }
+            KeyCode::Char(c) if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) => {
+                self.query.push(c);
+                self.refilter();
+            }
+            _ => {}
+        }
+        OverlayOutcome::Consumed
+    }
+}
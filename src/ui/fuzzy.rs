@@ -0,0 +1,93 @@
+/// Case-insensitive subsequence match of `query` in `candidate`.
+/// Returns a score (higher is better) and the char indices that matched, `None` if it does not match.
+/// Consecutive characters and matches at word starts (`_`, `-`, ` `, `/`, `.`) score higher.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<(i32, Vec<usize>)> {
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    let chars: Vec<char> = candidate.chars().collect();
+    let mut positions = Vec::new();
+    let mut score = 0;
+    let mut from = 0;
+    for q in query.chars().flat_map(char::to_lowercase) {
+        let idx = (from..chars.len()).find(|i| chars[*i].to_lowercase().eq([q]))?;
+
+        let at_word_start = idx == 0 || matches!(chars[idx - 1], '_' | '-' | ' ' | '/' | '.');
+        let consecutive = positions.last().is_some_and(|last| last + 1 == idx);
+        score += 1;
+        if at_word_start {
+            score += 8;
+        }
+        if consecutive {
+            score += 5;
+        }
+        score -= (idx - from) as i32; // skipped characters
+
+        positions.push(idx);
+        from = idx + 1;
+    }
+    // shorter candidates win ties
+    score -= (chars.len() / 8) as i32;
+    Some((score, positions))
+}
+
+/// Indices of the matching `candidates`, best first.
+pub fn fuzzy_filter<'a>(
+    query: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<(usize, Vec<usize>)> {
+    let mut matches: Vec<(i32, usize, Vec<usize>)> = candidates
+        .into_iter()
+        .enumerate()
+        .filter_map(|(idx, candidate)| {
+            fuzzy_match(query, candidate).map(|(score, positions)| (score, idx, positions))
+        })
+        .collect();
+    // stable, so equal scores keep their original order
+    matches.sort_by_key(|(score, _, _)| -score);
+    matches
+        .into_iter()
+        .map(|(_, idx, positions)| (idx, positions))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order<'a>(query: &str, candidates: &[&'a str]) -> Vec<&'a str> {
+        fuzzy_filter(query, candidates.iter().copied())
+            .into_iter()
+            .map(|(idx, _)| candidates[idx])
+            .collect()
+    }
+
+    #[test]
+    fn matches_case_insensitive_subsequences() {
+        assert_eq!(fuzzy_match("FB", "foo_bar").unwrap().1, [0, 4]);
+        assert_eq!(fuzzy_match("ß", "Straße").unwrap().1, [4]);
+        assert_eq!(fuzzy_match("ba", "abc"), None);
+        assert_eq!(fuzzy_match("", "anything"), Some((0, Vec::new())));
+    }
+
+    #[test]
+    fn word_starts_and_runs_rank_first() {
+        assert_eq!(order("b", &["abc", "a_b"]), ["a_b", "abc"]);
+        assert_eq!(order("ab", &["axb", "abx"]), ["abx", "axb"]);
+        assert_eq!(
+            order("main", &["src/domain.rs", "src/main.rs"]),
+            ["src/main.rs", "src/domain.rs"]
+        );
+    }
+
+    #[test]
+    fn ties_prefer_short_then_original_order() {
+        assert_eq!(
+            order("ab", &["ab_long_long_long", "ab"]),
+            ["ab", "ab_long_long_long"]
+        );
+        assert_eq!(order("", &["b", "a", "c"]), ["b", "a", "c"]);
+        assert!(order("z", &["a", "b"]).is_empty());
+    }
+}
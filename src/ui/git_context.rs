@@ -0,0 +1,76 @@
+use crate::{
+    back_logic::{git::GitContext, message_loop::Command},
+    ui::{
+        app_ui_state::{AppUIState, ChatMessage, CheckBoxEntry, ContextSource},
+        nask_center_input::send_prompt,
+        overlay::Toast,
+    },
+};
+
+const REVIEW_STAGED_PROMPT: &str = "Review my staged changes.";
+
+impl GitContext {
+    /// A checked context entry holding `content`.
+    pub fn entry(self, content: String) -> CheckBoxEntry {
+        CheckBoxEntry {
+            checked: true,
+            selected: false,
+            entry: self.title(),
+            content: Some(content),
+            source: ContextSource::Git(self),
+        }
+    }
+}
+
+/// Asks for a review of the staged changes once their diff is attached.
+pub fn review_staged(state: &mut AppUIState) {
+    (state.pump_message_loop)(Command::RunGit {
+        git: GitContext::Staged,
+        review: true,
+    });
+}
+
+/// Attaches the result of `/git` or of a review request, for a review the prompt goes out
+/// with the diff as it is instead of running git again.
+pub fn git_finished(
+    state: &mut AppUIState,
+    git: GitContext,
+    review: bool,
+    output: Result<String, String>,
+) {
+    let content = match output {
+        Ok(content) => content,
+        Err(e) if review => {
+            state.overlays.push(Toast::new(e));
+            return;
+        }
+        Err(e) => {
+            state
+                .chat_state
+                .chat_messages
+                .push(ChatMessage::error(format!("/git: {}", e)));
+            return;
+        }
+    };
+    if !review {
+        state
+            .overlays
+            .push(Toast::new(format!("attached {}", git.title())));
+        state.additional_context_state.attach(git.entry(content));
+        return;
+    }
+    if content.trim().is_empty() {
+        state.overlays.push(Toast::new("nothing staged"));
+        return;
+    }
+    let context_state = &mut state.additional_context_state;
+    context_state.fresh.push(ContextSource::Git(git.clone()));
+    context_state.attach(git.entry(content));
+
+    let prompt = String::from(REVIEW_STAGED_PROMPT);
+    state
+        .chat_state
+        .chat_messages
+        .push(ChatMessage::prompt(prompt.clone()));
+    send_prompt(state, prompt);
+}
@@ -0,0 +1,100 @@
+use crate::ui::key_bindings::HelpSection;
+use crate::ui::overlay::{Overlay, OverlayOutcome, centered_rect};
+use crate::ui::theme::Theme;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+use ratatui::widgets::{Block, Borders, Padding};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+};
+
+pub struct HelpOverlay {
+    sections: Vec<HelpSection>,
+    scroll: u16,
+}
+
+impl HelpOverlay {
+    pub fn new(sections: Vec<HelpSection>) -> Self {
+        Self {
+            sections,
+            scroll: 0,
+        }
+    }
+}
+
+const HELP_WIDTH: u16 = 64;
+const HELP_MARGIN: u16 = 2;
+const KEYS_COLUMN_WIDTH: usize = 22;
+
+impl Overlay for HelpOverlay {
+    fn area_rect(&self, frame: Rect) -> Rect {
+        centered_rect(
+            frame,
+            HELP_WIDTH,
+            frame.height.saturating_sub(HELP_MARGIN * 2),
+        )
+    }
+
+    fn render(&mut self, area: Rect, buf: &mut Buffer, theme: &Theme) {
+        let mut lines: Vec<Line> = Vec::new();
+        for section in &self.sections {
+            if !lines.is_empty() {
+                lines.push(Line::from(""));
+            }
+            lines.push(Line::from(Span::styled(
+                section.title,
+                Style::default()
+                    .fg(theme.accent)
+                    .add_modifier(Modifier::BOLD),
+            )));
+            for (keys, description) in &section.entries {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("  {:<width$}", keys, width = KEYS_COLUMN_WIDTH),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(*description),
+                ]));
+            }
+        }
+
+        let block = Block::default()
+            .title(" Keys ")
+            .title_bottom(
+                Line::from(format!(" j/k scroll {} Esc close ", theme.glyphs.separator))
+                    .right_aligned(),
+            )
+            .borders(Borders::ALL)
+            .border_set(theme.glyphs.border)
+            .border_style(Style::default().fg(theme.accent))
+            .padding(Padding::horizontal(1));
+
+        let visible = block.inner(area).height;
+        let max_scroll = (lines.len() as u16).saturating_sub(visible);
+        self.scroll = self.scroll.min(max_scroll);
+
+        Paragraph::new(lines)
+            .block(block)
+            .style(Style::default().bg(theme.surface))
+            .scroll((self.scroll, 0))
+            .render(area, buf);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> OverlayOutcome {
+        match key.code {
+            KeyCode::Char('k') | KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            // clamped while rendering
+            KeyCode::Char('j') | KeyCode::Down => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::Char('q') | KeyCode::Char('?') | KeyCode::F(1) => {
+                return OverlayOutcome::Close;
+            }
+            _ => {}
+        }
+        OverlayOutcome::Consumed
+    }
+}
@@ -0,0 +1,992 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+};
+
+use color_eyre::{
+    Result,
+    eyre::{bail, eyre},
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    config::app_config::KeysConfig,
+    ui::{
+        app_ui_state::{Focus, InputMode},
+        event_system::KeyOperationEvent,
+        vim_motions::{VimMotion, VimOperator, VimTextObject},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        match code {
+            // terminals report Ctrl with either case, shift is what tells <C-S-a> from <C-a>
+            KeyCode::Char(c)
+                if modifiers.contains(KeyModifiers::CONTROL) && c.is_ascii_alphabetic() =>
+            {
+                let modifiers = if c.is_ascii_uppercase() {
+                    modifiers | KeyModifiers::SHIFT
+                } else {
+                    modifiers
+                };
+                Self {
+                    code: KeyCode::Char(c.to_ascii_lowercase()),
+                    modifiers,
+                }
+            }
+            // shift is already part of the character (`A`) or the key code (BackTab)
+            KeyCode::Char(_) | KeyCode::BackTab => Self {
+                code,
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            _ => Self { code, modifiers },
+        }
+    }
+
+    // normal mode reads 1-9 as a count before any binding is looked up
+    fn is_count_digit(&self) -> bool {
+        matches!(self.code, KeyCode::Char('1'..='9'))
+            && !self
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+
+    pub fn from_event(key: &KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers & Self::BINDABLE)
+    }
+
+    const BINDABLE: KeyModifiers = KeyModifiers::CONTROL
+        .union(KeyModifiers::ALT)
+        .union(KeyModifiers::SHIFT);
+}
+
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("Esc", KeyCode::Esc),
+    ("CR", KeyCode::Enter),
+    ("Enter", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("BS", KeyCode::Backspace),
+    ("Space", KeyCode::Char(' ')),
+    ("lt", KeyCode::Char('<')),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("Del", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+];
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.code {
+            KeyCode::BackTab => String::from("S-Tab"),
+            KeyCode::F(n) => format!("F{}", n),
+            KeyCode::Char(c) if c != ' ' && c != '<' => c.to_string(),
+            code => NAMED_KEYS
+                .iter()
+                .find(|(_, named)| *named == code)
+                .map(|(name, _)| name.to_string())
+                .unwrap_or_else(|| format!("{:?}", code)),
+        };
+
+        let mut prefix = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            prefix.push_str("C-");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            prefix.push_str("A-");
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            prefix.push_str("S-");
+        }
+
+        if prefix.is_empty() && name.chars().count() == 1 {
+            write!(f, "{}", name)
+        } else {
+            write!(f, "<{}{}>", prefix, name)
+        }
+    }
+}
+
+fn parse_bracketed(inner: &str) -> Result<KeyChord, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = inner;
+    // the key itself may be `-`, so only split off known modifier prefixes
+    while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+        modifiers |= match rest.as_bytes()[0].to_ascii_uppercase() {
+            b'C' => KeyModifiers::CONTROL,
+            b'A' | b'M' => KeyModifiers::ALT,
+            b'S' => KeyModifiers::SHIFT,
+            other => return Err(format!("unknown modifier `{}-`", other as char)),
+        };
+        rest = &rest[2..];
+    }
+
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => {
+            let named = NAMED_KEYS
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(rest))
+                .map(|(_, code)| *code);
+            let function = rest
+                .strip_prefix(['F', 'f'])
+                .and_then(|n| n.parse::<u8>().ok())
+                .filter(|n| (1..=12).contains(n))
+                .map(KeyCode::F);
+            named
+                .or(function)
+                .ok_or_else(|| format!("unknown key `{}`", rest))?
+        }
+    };
+
+    Ok(match code {
+        // like vim, <C-X> is the same as <C-x>, only an explicit <C-S-x> keeps shift
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => {
+            KeyChord::new(KeyCode::Char(c.to_ascii_lowercase()), modifiers)
+        }
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+            KeyChord::new(KeyCode::Char(c.to_ascii_uppercase()), modifiers)
+        }
+        KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => {
+            KeyChord::new(KeyCode::BackTab, modifiers)
+        }
+        code => KeyChord::new(code, modifiers),
+    })
+}
+
+/// Parses vim key notation such as `q`, `<A-x>`, `<S-Tab>` or `<C-x><C-e>`.
+pub fn parse_key_sequence(notation: &str) -> Result<Vec<KeyChord>, String> {
+    let mut keys = Vec::new();
+    let mut rest = notation;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '<'
+            && let Some(end) = rest.find('>')
+            && end > 1
+        {
+            keys.push(parse_bracketed(&rest[1..end])?);
+            rest = &rest[end + 1..];
+        } else {
+            keys.push(KeyChord::new(KeyCode::Char(c), KeyModifiers::NONE));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    if keys.is_empty() {
+        return Err(String::from("empty key sequence"));
+    }
+    Ok(keys)
+}
+
+pub fn format_key_sequence(keys: &[KeyChord]) -> String {
+    keys.iter().map(KeyChord::to_string).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BindingScope {
+    Global,
+    Input,
+    InputNormal,
+    InputInsert,
+    Chat,
+    Contexts,
+    OperatorPending,
+}
+
+impl BindingScope {
+    pub fn config_name(self) -> &'static str {
+        match self {
+            BindingScope::Global => "global",
+            BindingScope::Input => "input",
+            BindingScope::InputNormal => "input_normal",
+            BindingScope::InputInsert => "input_insert",
+            BindingScope::Chat => "chat",
+            BindingScope::Contexts => "contexts",
+            BindingScope::OperatorPending => "operator_pending",
+        }
+    }
+
+    fn help_title(self) -> &'static str {
+        match self {
+            BindingScope::Global => "Everywhere",
+            BindingScope::Input => "Prompt",
+            BindingScope::InputNormal => "Prompt, normal mode",
+            BindingScope::InputInsert => "Prompt, insert mode",
+            BindingScope::Chat => "Chat (message selected)",
+            BindingScope::Contexts => "Context panel",
+            BindingScope::OperatorPending => "Prompt, after d/c/y",
+        }
+    }
+
+    // digits are counts in these scopes, they cannot be rebound
+    fn takes_count(self) -> bool {
+        matches!(
+            self,
+            BindingScope::InputNormal | BindingScope::OperatorPending
+        )
+    }
+
+    /// Scopes consulted for a key, most specific first.
+    pub fn lookup_order(focus: Focus, mode: InputMode) -> &'static [BindingScope] {
+        match (focus, mode) {
+            (Focus::Input, InputMode::Normal) => &[
+                BindingScope::InputNormal,
+                BindingScope::Input,
+                BindingScope::Global,
+            ],
+            (Focus::Input, InputMode::Insert) => &[
+                BindingScope::InputInsert,
+                BindingScope::Input,
+                BindingScope::Global,
+            ],
+            (Focus::Chat, _) => &[BindingScope::Chat, BindingScope::Global],
+            (Focus::Contexts, _) => &[BindingScope::Contexts, BindingScope::Global],
+        }
+    }
+
+    /// Scopes consulted while an operator waits for its motion or text object.
+    pub const OPERATOR_PENDING: &'static [BindingScope] = &[BindingScope::OperatorPending];
+
+    const ALL: [BindingScope; 7] = [
+        BindingScope::Global,
+        BindingScope::Input,
+        BindingScope::InputNormal,
+        BindingScope::InputInsert,
+        BindingScope::Chat,
+        BindingScope::Contexts,
+        BindingScope::OperatorPending,
+    ];
+
+    const LOOKUP_CHAINS: [(Focus, InputMode); 4] = [
+        (Focus::Input, InputMode::Normal),
+        (Focus::Input, InputMode::Insert),
+        (Focus::Chat, InputMode::Normal),
+        (Focus::Contexts, InputMode::Normal),
+    ];
+}
+
+/// Action names usable in `[keys.*]` with their help text, `nop` removes a default binding.
+pub const ACTIONS: &[(&str, KeyOperationEvent, &str)] = &[
+    (
+        "toggle_buffers",
+        KeyOperationEvent::ToggleBuffers,
+        "show or hide the context panel",
+    ),
+    (
+        "select_prev_buffer",
+        KeyOperationEvent::SelectPrevBuffer,
+        "select previous context",
+    ),
+    (
+        "select_next_buffer",
+        KeyOperationEvent::SelectNextBuffer,
+        "select next context",
+    ),
+    (
+        "check_buffer",
+        KeyOperationEvent::CheckSelectedBuffer,
+        "attach or detach selected context",
+    ),
+    (
+        "context_filter",
+        KeyOperationEvent::ContextFilterStart,
+        "filter the contexts by name",
+    ),
+    (
+        "file_picker",
+        KeyOperationEvent::ShowFilePicker,
+        "add project files as context",
+    ),
+    (
+        "tmux_panes",
+        KeyOperationEvent::ShowTmuxPanes,
+        "attach the scrollback of a tmux pane",
+    ),
+    (
+        "review_staged",
+        KeyOperationEvent::ReviewStaged,
+        "ask for a review of the staged changes",
+    ),
+    (
+        "submit",
+        KeyOperationEvent::InputSubmitted,
+        "send the prompt",
+    ),
+    (
+        "insert_mode",
+        KeyOperationEvent::InputChangeToInsertMode,
+        "insert mode",
+    ),
+    (
+        "normal_mode",
+        KeyOperationEvent::InputChangeToNormalMode,
+        "normal mode",
+    ),
+    (
+        "append",
+        KeyOperationEvent::InputAppend,
+        "append after cursor",
+    ),
+    (
+        "append_at_end",
+        KeyOperationEvent::InputAppendAtEnd,
+        "append at end of prompt",
+    ),
+    (
+        "insert_at_start",
+        KeyOperationEvent::InputInsertAtStart,
+        "insert at start of prompt",
+    ),
+    (
+        "open_line",
+        KeyOperationEvent::InputOpenLine,
+        "continue at end of prompt",
+    ),
+    (
+        "operator_delete",
+        KeyOperationEvent::VimOperator(VimOperator::Delete),
+        "delete {motion}",
+    ),
+    (
+        "operator_change",
+        KeyOperationEvent::VimOperator(VimOperator::Change),
+        "change {motion}",
+    ),
+    (
+        "operator_yank",
+        KeyOperationEvent::VimOperator(VimOperator::Yank),
+        "yank {motion}",
+    ),
+    (
+        "left",
+        KeyOperationEvent::VimMotion(VimMotion::Left),
+        "character left",
+    ),
+    (
+        "right",
+        KeyOperationEvent::VimMotion(VimMotion::Right),
+        "character right",
+    ),
+    (
+        "word_forward",
+        KeyOperationEvent::VimMotion(VimMotion::WordForward),
+        "start of next word",
+    ),
+    (
+        "word_backward",
+        KeyOperationEvent::VimMotion(VimMotion::WordBackward),
+        "start of previous word",
+    ),
+    (
+        "word_end",
+        KeyOperationEvent::VimMotion(VimMotion::WordEnd),
+        "end of word",
+    ),
+    (
+        "line_start",
+        KeyOperationEvent::VimMotion(VimMotion::LineStart),
+        "start of line",
+    ),
+    (
+        "line_end",
+        KeyOperationEvent::VimMotion(VimMotion::LineEnd),
+        "end of line",
+    ),
+    (
+        "inner_word",
+        KeyOperationEvent::VimTextObject(VimTextObject::InnerWord),
+        "inner word",
+    ),
+    (
+        "delete_char",
+        KeyOperationEvent::VimDeleteChar,
+        "delete character",
+    ),
+    (
+        "put_after",
+        KeyOperationEvent::VimPutAfter,
+        "put after cursor",
+    ),
+    (
+        "put_before",
+        KeyOperationEvent::VimPutBefore,
+        "put before cursor",
+    ),
+    (
+        "vim_cancel",
+        KeyOperationEvent::VimCancel,
+        "cancel pending count/operator",
+    ),
+    (
+        "history_older",
+        KeyOperationEvent::HistoryOlder,
+        "older prompt from history",
+    ),
+    (
+        "history_newer",
+        KeyOperationEvent::HistoryNewer,
+        "newer prompt from history",
+    ),
+    (
+        "history_search",
+        KeyOperationEvent::HistorySearchStart,
+        "search prompt history",
+    ),
+    (
+        "open_external_editor",
+        KeyOperationEvent::OpenExternalEditor,
+        "edit prompt in $EDITOR",
+    ),
+    (
+        "chat_scroll_up",
+        KeyOperationEvent::ChatScrollUp,
+        "scroll chat up",
+    ),
+    (
+        "chat_scroll_down",
+        KeyOperationEvent::ChatScrollDown,
+        "scroll chat down",
+    ),
+    (
+        "chat_search_forward",
+        KeyOperationEvent::ChatSearchForward,
+        "search chat forward",
+    ),
+    (
+        "chat_search_backward",
+        KeyOperationEvent::ChatSearchBackward,
+        "search chat backward",
+    ),
+    (
+        "chat_search_next",
+        KeyOperationEvent::ChatSearchNext,
+        "next search hit",
+    ),
+    (
+        "chat_search_prev",
+        KeyOperationEvent::ChatSearchPrev,
+        "previous search hit",
+    ),
+    (
+        "chat_search_clear",
+        KeyOperationEvent::ChatSearchClear,
+        "clear chat search",
+    ),
+    (
+        "focus_next",
+        KeyOperationEvent::FocusNext,
+        "focus next pane",
+    ),
+    (
+        "focus_prev",
+        KeyOperationEvent::FocusPrev,
+        "focus previous pane",
+    ),
+    (
+        "focus_chat",
+        KeyOperationEvent::FocusChat,
+        "select chat messages",
+    ),
+    (
+        "focus_input",
+        KeyOperationEvent::FocusInput,
+        "back to the prompt",
+    ),
+    (
+        "chat_select_older",
+        KeyOperationEvent::ChatSelectOlder,
+        "select older message",
+    ),
+    (
+        "chat_select_newer",
+        KeyOperationEvent::ChatSelectNewer,
+        "select newer message",
+    ),
+    (
+        "chat_select_oldest",
+        KeyOperationEvent::ChatSelectOldest,
+        "select first message",
+    ),
+    (
+        "chat_select_newest",
+        KeyOperationEvent::ChatSelectNewest,
+        "select last message",
+    ),
+    (
+        "chat_yank_message",
+        KeyOperationEvent::ChatYankMessage,
+        "copy message",
+    ),
+    (
+        "chat_yank_code",
+        KeyOperationEvent::ChatYankCode,
+        "copy code blocks of message",
+    ),
+    (
+        "chat_delete_message",
+        KeyOperationEvent::ChatDeleteMessage,
+        "delete message",
+    ),
+    (
+        "chat_regenerate",
+        KeyOperationEvent::ChatRegenerate,
+        "regenerate answer",
+    ),
+    (
+        "chat_edit_resend",
+        KeyOperationEvent::ChatEditResend,
+        "edit prompt and resend",
+    ),
+    ("help", KeyOperationEvent::ShowHelp, "show this help"),
+    (
+        "command_palette",
+        KeyOperationEvent::ShowCommandPalette,
+        "search and run any action",
+    ),
+    ("quit", KeyOperationEvent::Quit, "quit"),
+    ("nop", KeyOperationEvent::Noop, "unbound"),
+];
+
+pub fn action_name(event: KeyOperationEvent) -> Option<&'static str> {
+    ACTIONS
+        .iter()
+        .find(|(_, action, _)| *action == event)
+        .map(|(name, _, _)| *name)
+}
+
+fn action_from_name(name: &str) -> Option<KeyOperationEvent> {
+    ACTIONS
+        .iter()
+        .find(|(action, _, _)| *action == name)
+        .map(|(_, event, _)| *event)
+}
+
+const DEFAULT_BINDINGS: &[(BindingScope, &str, &str)] = &[
+    (BindingScope::Global, "<Tab>", "focus_next"),
+    (BindingScope::Global, "<S-Tab>", "focus_prev"),
+    (BindingScope::Global, "<A-x>", "toggle_buffers"),
+    (BindingScope::Global, "<C-x><C-e>", "open_external_editor"),
+    (BindingScope::Global, "<F1>", "help"),
+    (BindingScope::Global, "<C-p>", "command_palette"),
+    (BindingScope::Global, "<C-o>", "file_picker"),
+    (BindingScope::Global, "<A-r>", "review_staged"),
+    (BindingScope::Input, "<C-r>", "history_search"),
+    (BindingScope::Input, "<PageUp>", "chat_scroll_up"),
+    (BindingScope::Input, "<PageDown>", "chat_scroll_down"),
+    (BindingScope::InputInsert, "<Up>", "history_older"),
+    (BindingScope::InputInsert, "<Down>", "history_newer"),
+    (BindingScope::InputInsert, "<Esc>", "normal_mode"),
+    (BindingScope::InputInsert, "<CR>", "submit"),
+    (BindingScope::InputNormal, "i", "insert_mode"),
+    (BindingScope::InputNormal, "q", "quit"),
+    (BindingScope::InputNormal, "<Esc>", "vim_cancel"),
+    (BindingScope::InputNormal, "a", "append"),
+    (BindingScope::InputNormal, "A", "append_at_end"),
+    (BindingScope::InputNormal, "I", "insert_at_start"),
+    (BindingScope::InputNormal, "o", "open_line"),
+    (BindingScope::InputNormal, "v", "focus_chat"),
+    (BindingScope::InputNormal, "/", "chat_search_forward"),
+    (BindingScope::InputNormal, "?", "chat_search_backward"),
+    (BindingScope::InputNormal, "g?", "help"),
+    (BindingScope::InputNormal, ":", "command_palette"),
+    (BindingScope::InputNormal, "n", "chat_search_next"),
+    (BindingScope::InputNormal, "N", "chat_search_prev"),
+    (BindingScope::InputNormal, "k", "history_older"),
+    (BindingScope::InputNormal, "j", "history_newer"),
+    (BindingScope::InputNormal, "x", "delete_char"),
+    (BindingScope::InputNormal, "p", "put_after"),
+    (BindingScope::InputNormal, "P", "put_before"),
+    (BindingScope::InputNormal, "d", "operator_delete"),
+    (BindingScope::InputNormal, "c", "operator_change"),
+    (BindingScope::InputNormal, "y", "operator_yank"),
+    (BindingScope::InputNormal, "h", "left"),
+    (BindingScope::InputNormal, "l", "right"),
+    (BindingScope::InputNormal, "w", "word_forward"),
+    (BindingScope::InputNormal, "b", "word_backward"),
+    (BindingScope::InputNormal, "e", "word_end"),
+    (BindingScope::InputNormal, "0", "line_start"),
+    (BindingScope::InputNormal, "$", "line_end"),
+    (BindingScope::OperatorPending, "h", "left"),
+    (BindingScope::OperatorPending, "l", "right"),
+    (BindingScope::OperatorPending, "w", "word_forward"),
+    (BindingScope::OperatorPending, "b", "word_backward"),
+    (BindingScope::OperatorPending, "e", "word_end"),
+    (BindingScope::OperatorPending, "0", "line_start"),
+    (BindingScope::OperatorPending, "$", "line_end"),
+    (BindingScope::OperatorPending, "iw", "inner_word"),
+    (BindingScope::OperatorPending, "d", "operator_delete"),
+    (BindingScope::OperatorPending, "c", "operator_change"),
+    (BindingScope::OperatorPending, "y", "operator_yank"),
+    (BindingScope::OperatorPending, "<Esc>", "vim_cancel"),
+    (BindingScope::Chat, "k", "chat_select_older"),
+    (BindingScope::Chat, "<Up>", "chat_select_older"),
+    (BindingScope::Chat, "j", "chat_select_newer"),
+    (BindingScope::Chat, "<Down>", "chat_select_newer"),
+    (BindingScope::Chat, "g", "chat_select_oldest"),
+    (BindingScope::Chat, "<Home>", "chat_select_oldest"),
+    (BindingScope::Chat, "G", "chat_select_newest"),
+    (BindingScope::Chat, "<End>", "chat_select_newest"),
+    (BindingScope::Chat, "y", "chat_yank_message"),
+    (BindingScope::Chat, "Y", "chat_yank_code"),
+    (BindingScope::Chat, "d", "chat_delete_message"),
+    (BindingScope::Chat, "r", "chat_regenerate"),
+    (BindingScope::Chat, "e", "chat_edit_resend"),
+    (BindingScope::Chat, "/", "chat_search_forward"),
+    (BindingScope::Chat, "?", "chat_search_backward"),
+    (BindingScope::Chat, ":", "command_palette"),
+    (BindingScope::Chat, "n", "chat_search_next"),
+    (BindingScope::Chat, "N", "chat_search_prev"),
+    (BindingScope::Chat, "<PageUp>", "chat_scroll_up"),
+    (BindingScope::Chat, "<PageDown>", "chat_scroll_down"),
+    (BindingScope::Chat, "<Esc>", "focus_input"),
+    (BindingScope::Chat, "i", "focus_input"),
+    (BindingScope::Chat, "q", "focus_input"),
+    (BindingScope::Contexts, "k", "select_prev_buffer"),
+    (BindingScope::Contexts, "<Up>", "select_prev_buffer"),
+    (BindingScope::Contexts, "j", "select_next_buffer"),
+    (BindingScope::Contexts, "<Down>", "select_next_buffer"),
+    (BindingScope::Contexts, "/", "context_filter"),
+    (BindingScope::Contexts, "a", "file_picker"),
+    (BindingScope::Contexts, "t", "tmux_panes"),
+    (BindingScope::Contexts, ":", "command_palette"),
+    (BindingScope::Contexts, "<Space>", "check_buffer"),
+    (BindingScope::Contexts, "x", "check_buffer"),
+    (BindingScope::Contexts, "<CR>", "check_buffer"),
+    (BindingScope::Contexts, "<PageUp>", "chat_scroll_up"),
+    (BindingScope::Contexts, "<PageDown>", "chat_scroll_down"),
+    (BindingScope::Contexts, "<Esc>", "focus_input"),
+    (BindingScope::Contexts, "i", "focus_input"),
+    (BindingScope::Contexts, "q", "focus_input"),
+];
+
+pub struct HelpSection {
+    pub title: &'static str,
+    pub entries: Vec<(String, &'static str)>, // (keys, description)
+}
+
+pub enum KeyLookup {
+    Matched(KeyOperationEvent),
+    Prefix, // the keys so far start a longer binding
+    NoMatch,
+}
+
+type ScopeTable = HashMap<Vec<KeyChord>, KeyOperationEvent>;
+
+pub struct KeyBindings {
+    table: HashMap<BindingScope, ScopeTable>,
+}
+
+impl KeyBindings {
+    /// Defaults merged with the `[keys.*]` overrides, fails on unknown keys/actions and on conflicts.
+    pub fn new(config: &KeysConfig) -> Result<Self> {
+        let mut table: HashMap<BindingScope, ScopeTable> = HashMap::new();
+        for (scope, notation, action) in DEFAULT_BINDINGS {
+            let keys = parse_key_sequence(notation)
+                .map_err(|e| eyre!("default binding `{}`: {}", notation, e))?;
+            let event = action_from_name(action)
+                .ok_or_else(|| eyre!("default binding `{}`: unknown action", notation))?;
+            table.entry(*scope).or_default().insert(keys, event);
+        }
+
+        let overrides = [
+            (BindingScope::Global, &config.global),
+            (BindingScope::Input, &config.input),
+            (BindingScope::InputNormal, &config.input_normal),
+            (BindingScope::InputInsert, &config.input_insert),
+            (BindingScope::Chat, &config.chat),
+            (BindingScope::Contexts, &config.contexts),
+            (BindingScope::OperatorPending, &config.operator_pending),
+        ];
+
+        let mut conflicts = BTreeSet::new();
+        for (scope, bindings) in overrides {
+            let scope_table = table.entry(scope).or_default();
+            // `<C-c>` and `<c-c>` are the same key, report them instead of letting one win silently
+            let mut seen: HashMap<Vec<KeyChord>, &str> = HashMap::new();
+
+            for (notation, action) in bindings {
+                let keys = parse_key_sequence(notation)
+                    .map_err(|e| eyre!("[keys.{}] `{}`: {}", scope.config_name(), notation, e))?;
+                let event = action_from_name(action).ok_or_else(|| {
+                    eyre!(
+                        "[keys.{}] `{}`: unknown action `{}`",
+                        scope.config_name(),
+                        notation,
+                        action
+                    )
+                })?;
+
+                if let Some(previous) = seen.insert(keys.clone(), notation) {
+                    conflicts.insert(format!(
+                        "[keys.{}] `{}` and `{}` are the same key",
+                        scope.config_name(),
+                        previous,
+                        notation
+                    ));
+                }
+
+                if scope.takes_count()
+                    && event != KeyOperationEvent::Noop
+                    && keys[0].is_count_digit()
+                {
+                    conflicts.insert(format!(
+                        "[keys.{}] `{}` starts with a digit, which is read as a count there",
+                        scope.config_name(),
+                        notation
+                    ));
+                }
+
+                if event == KeyOperationEvent::Noop {
+                    scope_table.remove(&keys);
+                } else {
+                    scope_table.insert(keys, event);
+                }
+            }
+        }
+
+        let bindings = Self { table };
+        conflicts.extend(bindings.prefix_conflicts());
+        if !conflicts.is_empty() {
+            let report: Vec<String> = conflicts.into_iter().collect();
+            bail!("conflicting key bindings:\n  {}", report.join("\n  "));
+        }
+        Ok(bindings)
+    }
+
+    // a binding that is a prefix of another one, seen from the same focus/mode,
+    // makes the longer one unreachable
+    fn prefix_conflicts(&self) -> BTreeSet<String> {
+        let mut conflicts = BTreeSet::new();
+
+        let chains = BindingScope::LOOKUP_CHAINS
+            .iter()
+            .map(|(focus, mode)| BindingScope::lookup_order(*focus, *mode))
+            .chain([BindingScope::OPERATOR_PENDING]);
+        for chain in chains {
+            let visible: Vec<(BindingScope, &Vec<KeyChord>, KeyOperationEvent)> = chain
+                .iter()
+                .flat_map(|scope| {
+                    self.table
+                        .get(scope)
+                        .into_iter()
+                        .flatten()
+                        .map(move |(keys, event)| (*scope, keys, *event))
+                })
+                .collect();
+
+            for (short_scope, short, short_event) in &visible {
+                for (long_scope, long, long_event) in &visible {
+                    if long.len() > short.len() && long.starts_with(short) {
+                        conflicts.insert(format!(
+                            "`{}` ({}) in [keys.{}] hides `{}` ({}) in [keys.{}]",
+                            format_key_sequence(short),
+                            action_name(*short_event).unwrap_or("?"),
+                            short_scope.config_name(),
+                            format_key_sequence(long),
+                            action_name(*long_event).unwrap_or("?"),
+                            long_scope.config_name(),
+                        ));
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    pub fn lookup(&self, scopes: &[BindingScope], keys: &[KeyChord]) -> KeyLookup {
+        for scope in scopes {
+            let Some(scope_table) = self.table.get(scope) else {
+                continue;
+            };
+            if let Some(event) = scope_table.get(keys) {
+                return KeyLookup::Matched(*event);
+            }
+            if scope_table
+                .keys()
+                .any(|bound| bound.len() > keys.len() && bound.starts_with(keys))
+            {
+                return KeyLookup::Prefix;
+            }
+        }
+        KeyLookup::NoMatch
+    }
+    /// Every key bound to `event` in any scope, plain keys before named ones.
+    pub fn keys_for(&self, event: KeyOperationEvent) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .table
+            .values()
+            .flatten()
+            .filter(|(_, bound)| **bound == event)
+            .map(|(keys, _)| format_key_sequence(keys))
+            .collect();
+        keys.sort_by_key(|k| (k.starts_with('<'), k.clone()));
+        keys.dedup();
+        keys
+    }
+
+    /// The active bindings per scope, keys bound to the same action share a row.
+    pub fn help_sections(&self) -> Vec<HelpSection> {
+        BindingScope::ALL
+            .iter()
+            .filter_map(|scope| {
+                let scope_table = self.table.get(scope)?;
+                let mut entries: Vec<(String, &'static str)> = ACTIONS
+                    .iter()
+                    .filter_map(|(_, action, description)| {
+                        let mut keys: Vec<String> = scope_table
+                            .iter()
+                            .filter(|(_, event)| *event == action)
+                            .map(|(keys, _)| format_key_sequence(keys))
+                            .collect();
+                        if keys.is_empty() {
+                            return None;
+                        }
+                        // plain keys before named ones, as `k, <Up>`
+                        keys.sort_by_key(|k| (k.starts_with('<'), k.clone()));
+                        Some((keys.join(", "), *description))
+                    })
+                    .collect();
+                if scope.takes_count() {
+                    entries.push((String::from("1-9"), "count, repeats what follows"));
+                }
+
+                (!entries.is_empty()).then(|| HelpSection {
+                    title: scope.help_title(),
+                    entries,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> KeyBindings {
+        KeyBindings::new(&KeysConfig::default()).unwrap()
+    }
+
+    fn chords(notation: &str) -> Vec<KeyChord> {
+        parse_key_sequence(notation).unwrap()
+    }
+
+    #[test]
+    fn operator_pending_keys_come_from_the_table() {
+        let bindings = defaults();
+        let lookup = |notation| bindings.lookup(BindingScope::OPERATOR_PENDING, &chords(notation));
+
+        assert!(matches!(
+            lookup("w"),
+            KeyLookup::Matched(KeyOperationEvent::VimMotion(VimMotion::WordForward))
+        ));
+        assert!(matches!(lookup("i"), KeyLookup::Prefix));
+        assert!(matches!(
+            lookup("iw"),
+            KeyLookup::Matched(KeyOperationEvent::VimTextObject(VimTextObject::InnerWord))
+        ));
+        assert!(matches!(lookup("x"), KeyLookup::NoMatch));
+    }
+
+    #[test]
+    fn motions_can_be_rebound() {
+        let config = KeysConfig {
+            operator_pending: [(String::from("W"), String::from("word_forward"))].into(),
+            ..KeysConfig::default()
+        };
+        let bindings = KeyBindings::new(&config).unwrap();
+        assert!(matches!(
+            bindings.lookup(BindingScope::OPERATOR_PENDING, &chords("W")),
+            KeyLookup::Matched(KeyOperationEvent::VimMotion(VimMotion::WordForward))
+        ));
+    }
+
+    #[test]
+    fn question_mark_searches_backward_in_normal_mode() {
+        let bindings = defaults();
+        let scopes = BindingScope::lookup_order(Focus::Input, InputMode::Normal);
+        assert!(matches!(
+            bindings.lookup(scopes, &chords("?")),
+            KeyLookup::Matched(KeyOperationEvent::ChatSearchBackward)
+        ));
+        assert!(matches!(
+            bindings.lookup(scopes, &chords("g?")),
+            KeyLookup::Matched(KeyOperationEvent::ShowHelp)
+        ));
+    }
+
+    #[test]
+    fn help_lists_motions_counts_and_text_objects() {
+        let sections = defaults().help_sections();
+        let pending = sections
+            .iter()
+            .find(|s| s.title == BindingScope::OperatorPending.help_title())
+            .unwrap();
+        for keys in ["w", "$", "iw", "1-9"] {
+            assert!(pending.entries.iter().any(|(k, _)| k == keys), "{}", keys);
+        }
+    }
+
+    #[test]
+    fn explicit_shift_stays_with_ctrl() {
+        let ctrl_shift_a = chords("<C-S-a>");
+        assert_eq!(
+            ctrl_shift_a,
+            [KeyChord {
+                code: KeyCode::Char('a'),
+                modifiers: KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+            }]
+        );
+        assert_ne!(ctrl_shift_a, chords("<C-a>"));
+        assert_eq!(chords("<C-A>"), chords("<C-a>"));
+        assert_eq!(format_key_sequence(&ctrl_shift_a), "<C-S-a>");
+
+        // terminals differ in the case they report with Ctrl and Shift held
+        for code in [KeyCode::Char('A'), KeyCode::Char('a')] {
+            let event = KeyEvent::new(code, KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+            assert_eq!(KeyChord::from_event(&event), ctrl_shift_a[0], "{:?}", code);
+        }
+        let plain = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        assert_eq!(KeyChord::from_event(&plain), chords("<C-a>")[0]);
+    }
+
+    #[test]
+    fn digits_cannot_be_bound_where_counts_are_typed() {
+        for (scope, notation) in [("input_normal", "5"), ("operator_pending", "2w")] {
+            let binding = [(String::from(notation), String::from("help"))].into();
+            let config = match scope {
+                "input_normal" => KeysConfig {
+                    input_normal: binding,
+                    ..KeysConfig::default()
+                },
+                _ => KeysConfig {
+                    operator_pending: binding,
+                    ..KeysConfig::default()
+                },
+            };
+            let error = KeyBindings::new(&config).err().expect(notation).to_string();
+            assert!(error.contains("read as a count"), "{}", error);
+        }
+
+        // with a modifier, or in insert mode, a digit is an ordinary key
+        let config = KeysConfig {
+            input_normal: [(String::from("<C-5>"), String::from("help"))].into(),
+            input_insert: [(String::from("<A-5>"), String::from("help"))].into(),
+            ..KeysConfig::default()
+        };
+        assert!(KeyBindings::new(&config).is_ok());
+    }
+}
@@ -0,0 +1,174 @@
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Margin, Rect},
+    text::Line,
+};
+
+use crate::ui::{app_ui_state::AppUIState, nask_center_banner::banner_height};
+
+pub const INPUT_HEIGHT: u16 = 5;
+const META_INFO_HEIGHT: u16 = 1;
+const CONTEXTS_HEIGHT: u16 = 1;
+const STATUS_HEIGHT: u16 = 1;
+const CONTEXTS_PANEL_MAX_ROWS: u16 = 8;
+const CENTER_WIDTH: u16 = 70;
+const CHAT_PAD: u16 = 1;
+
+/// Below this many columns paddings, the banner and secondary info are dropped.
+pub const NARROW_WIDTH: u16 = 40;
+
+/// Regions of the frame, recomputed every draw from what is shown.
+/// Regions that are not shown (banner while chatting, chat while empty) are zero sized.
+#[derive(Default, Clone, Copy)]
+pub struct AppLayout {
+    pub meta_info: Rect,
+    pub banner: Rect,
+    pub chat: Rect,
+    pub input: Rect,
+    pub contexts: Rect,
+    pub status: Rect,
+}
+
+pub fn is_narrow(area: Rect) -> bool {
+    area.width < NARROW_WIDTH
+}
+
+// the expanded panel grows with its entries, up to half the screen
+fn contexts_height(area: Rect, state: &AppUIState) -> u16 {
+    let context_state = &state.additional_context_state;
+    if context_state.collapsed {
+        return CONTEXTS_HEIGHT;
+    }
+    let rows = (context_state.visible().len() as u16).clamp(1, CONTEXTS_PANEL_MAX_ROWS);
+    (rows + 2).min(area.height / 2).max(CONTEXTS_HEIGHT)
+}
+
+impl AppLayout {
+    pub fn compute(area: Rect, state: &AppUIState) -> Self {
+        let [meta_info, body, contexts, status] = Layout::vertical([
+            Constraint::Length(META_INFO_HEIGHT),
+            Constraint::Fill(1),
+            Constraint::Length(contexts_height(area, state)),
+            Constraint::Length(STATUS_HEIGHT),
+        ])
+        .areas(area);
+
+        let layout = Self {
+            meta_info,
+            contexts,
+            status,
+            ..Self::default()
+        };
+        if state.chat_state.chat_messages.is_empty() {
+            layout.with_centered_input(body, state.theme.glyphs.banner)
+        } else {
+            layout.with_chat(body)
+        }
+    }
+
+    // the start screen: banner above the input, slightly above the middle
+    fn with_centered_input(self, body: Rect, banner: &str) -> Self {
+        let [column] = Layout::horizontal([Constraint::Max(CENTER_WIDTH)])
+            .flex(Flex::Center)
+            .areas(body);
+
+        let banner_width = banner
+            .lines()
+            .map(|l| Line::from(l).width())
+            .max()
+            .unwrap_or(0) as u16;
+        let show_banner = !is_narrow(body)
+            && column.width >= banner_width
+            && body.height >= banner_height() + INPUT_HEIGHT;
+        let banner_h = if show_banner { banner_height() } else { 0 };
+
+        let content_h = (banner_h + INPUT_HEIGHT).min(column.height);
+        let top = (column.height - content_h) * 2 / 5;
+        let [_, banner, input, _] = Layout::vertical([
+            Constraint::Length(top),
+            Constraint::Length(banner_h),
+            Constraint::Length(INPUT_HEIGHT),
+            Constraint::Fill(1),
+        ])
+        .areas(column);
+
+        Self {
+            banner,
+            input,
+            ..self
+        }
+    }
+
+    fn with_chat(self, body: Rect) -> Self {
+        let [chat, input] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(INPUT_HEIGHT)]).areas(body);
+
+        let pad = if is_narrow(body) { 0 } else { CHAT_PAD };
+        Self {
+            chat: chat.inner(Margin::new(pad, pad)),
+            input,
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+    use ratatui::{Terminal, backend::TestBackend};
+
+    use super::*;
+    use crate::ui::{app_ui_state::ChatMessage, chat::create_nask_chat};
+
+    fn draw(width: u16, height: u16, state: &mut AppUIState) -> TestBackend {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| crate::render(frame, state, &create_nask_chat()))
+            .unwrap();
+        terminal.backend().clone()
+    }
+
+    fn start_screen(width: u16, height: u16) -> TestBackend {
+        draw(width, height, &mut AppUIState::new(|_| {}))
+    }
+
+    fn chat_screen(width: u16, height: u16) -> TestBackend {
+        let mut state = AppUIState::new(|_| {});
+        state.chat_state.chat_messages = vec![
+            ChatMessage::prompt(String::from("How do I reverse a Vec?")),
+            ChatMessage::error(String::from("the model is not reachable")),
+        ];
+        draw(width, height, &mut state)
+    }
+
+    #[test]
+    fn tiny() {
+        assert_snapshot!("start_30x10", start_screen(30, 10));
+        assert_snapshot!("chat_30x10", chat_screen(30, 10));
+    }
+
+    #[test]
+    fn just_below_narrow() {
+        assert!(is_narrow(Rect::new(0, 0, NARROW_WIDTH - 1, 20)));
+        assert_snapshot!("start_39x20", start_screen(NARROW_WIDTH - 1, 20));
+        assert_snapshot!("chat_39x20", chat_screen(NARROW_WIDTH - 1, 20));
+    }
+
+    #[test]
+    fn at_narrow() {
+        assert!(!is_narrow(Rect::new(0, 0, NARROW_WIDTH, 20)));
+        assert_snapshot!("start_40x20", start_screen(NARROW_WIDTH, 20));
+        assert_snapshot!("chat_40x20", chat_screen(NARROW_WIDTH, 20));
+    }
+
+    #[test]
+    fn standard() {
+        assert_snapshot!("start_80x24", start_screen(80, 24));
+        assert_snapshot!("chat_80x24", chat_screen(80, 24));
+    }
+
+    #[test]
+    fn large() {
+        assert_snapshot!("start_200x60", start_screen(200, 60));
+        assert_snapshot!("chat_200x60", chat_screen(200, 60));
+    }
+}
@@ -0,0 +1,83 @@
+use ratatui::{
+    style::{Modifier, Style},
+    text::Span,
+};
+
+use crate::ui::theme::Theme;
+
+/// The row after `current`, or before it when `backward`, wrapping around at either end.
+/// `current` is kept while the list is empty.
+pub fn wrap_step(current: usize, len: usize, backward: bool) -> usize {
+    if len == 0 {
+        current
+    } else if backward {
+        (current + len - 1) % len
+    } else {
+        (current + 1) % len
+    }
+}
+
+/// The first visible row once `scroll` moved just far enough that `selected` is one of
+/// the `height` rows in view.
+pub fn scroll_into_view(scroll: usize, selected: usize, height: usize) -> usize {
+    if selected < scroll {
+        selected
+    } else if height > 0 && selected >= scroll + height {
+        selected + 1 - height
+    } else {
+        scroll
+    }
+}
+
+/// One span per char of `text`, the fuzzy matched `positions` in bold accent.
+pub fn highlight_spans(text: &str, positions: &[usize], theme: &Theme) -> Vec<Span<'static>> {
+    text.chars()
+        .enumerate()
+        .map(|(idx, c)| {
+            let style = if positions.contains(&idx) {
+                Style::default()
+                    .fg(theme.accent)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            Span::styled(c.to_string(), style)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_step_wraps_at_both_ends() {
+        assert_eq!(wrap_step(0, 3, false), 1);
+        assert_eq!(wrap_step(2, 3, false), 0);
+        assert_eq!(wrap_step(0, 3, true), 2);
+        assert_eq!(wrap_step(5, 0, true), 5);
+    }
+
+    #[test]
+    fn scroll_into_view_moves_only_as_far_as_needed() {
+        // already visible
+        assert_eq!(scroll_into_view(2, 4, 5), 2);
+        // above the window
+        assert_eq!(scroll_into_view(3, 1, 5), 1);
+        // below the window, it becomes the last visible row
+        assert_eq!(scroll_into_view(0, 7, 5), 3);
+        // nothing is visible without height
+        assert_eq!(scroll_into_view(2, 9, 0), 2);
+    }
+
+    #[test]
+    fn highlight_spans_marks_matched_chars() {
+        let theme = Theme::default();
+        let spans = highlight_spans("häy", &[1], &theme);
+        let text: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, ["h", "ä", "y"]);
+        assert_eq!(spans[0].style, Style::default());
+        assert_eq!(spans[1].style.fg, Some(theme.accent));
+        assert!(spans[1].style.add_modifier.contains(Modifier::BOLD));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::ui::app_ui_state::AppUIState;
+use crate::ui::layout::is_narrow;
 use crate::ui::renderable_trait::Renderable;
-use ratatui::style::Color;
 use ratatui::text::Line;
 use ratatui::text::Span;
 use ratatui::widgets::Paragraph;
@@ -18,41 +18,36 @@ pub fn create_meta_info() -> &'static dyn Renderable {
     &META
 }
 
-const META_INFO_WIDTH: u16 = 90;
-const META_INFO_HEIGHT: u16 = 5;
-const META_INFO_TOP_PAD: u16 = 0;
 const META_INFO_RIGHT_PAD: u16 = 5;
 
 impl Renderable for MetaInfo {
     fn area_rect(&self, area: Rect) -> Rect {
+        let pad = if is_narrow(area) {
+            0
+        } else {
+            META_INFO_RIGHT_PAD
+        };
         Rect {
-            x: area.x
-                + area
-                    .width
-                    .saturating_sub(META_INFO_WIDTH)
-                    .saturating_sub(META_INFO_RIGHT_PAD),
-            y: area.y + META_INFO_TOP_PAD,
-            width: META_INFO_WIDTH.min(area.width),
-            height: META_INFO_HEIGHT.min(area.height),
+            width: area.width.saturating_sub(pad),
+            ..area
         }
     }
 
     fn render(&self, area: Rect, buf: &mut Buffer, state: &mut AppUIState) {
         let meta_info_state = &state.meta_info_state;
+        let dim = Style::default()
+            .fg(state.theme.dim)
+            .add_modifier(Modifier::DIM);
 
-        let line = Line::from(vec![
-            Span::styled(
-                format!("model: {}", meta_info_state.model_name),
-                Style::default().fg(Color::Gray).add_modifier(Modifier::DIM),
-            ),
-            Span::raw("  ·  "),
-            Span::styled(
-                meta_info_state.endpoint.as_str(),
-                Style::default()
-                    .fg(Color::DarkGray)
-                    .add_modifier(Modifier::DIM),
-            ),
+        let mut line = Line::from(vec![
+            Span::styled(format!("model: {}", meta_info_state.model_name), dim),
+            Span::raw(format!("  {}  ", state.theme.glyphs.separator)),
+            Span::styled(meta_info_state.endpoint.as_str(), dim),
         ]);
+        // the endpoint is the first thing to go when space runs out
+        if is_narrow(area) || line.width() > area.width as usize {
+            line.spans.truncate(1);
+        }
 
         Paragraph::new(line)
             .alignment(Alignment::Right)
@@ -1,9 +1,30 @@
 pub mod app_ui_state;
-pub mod common;
+pub mod cargo_diagnostics;
+pub mod chat;
+pub mod chat_search;
+pub mod clipboard;
+pub mod command_palette;
+pub mod completion;
+pub mod conversation;
 pub mod event_system;
+pub mod external_editor;
+pub mod file_picker;
+pub mod fuzzy;
+pub mod git_context;
+pub mod help_overlay;
+pub mod key_bindings;
+pub mod layout;
+pub mod list_view;
 pub mod meta_info;
-pub mod nask_center;
 pub mod nask_center_banner;
 pub mod nask_center_input;
 pub mod nvim_buffers;
+pub mod overlay;
+pub mod prompt_history;
 pub mod renderable_trait;
+pub mod slash_commands;
+pub mod status_line;
+pub mod terminal_caps;
+pub mod theme;
+pub mod tmux_panes;
+pub mod vim_motions;
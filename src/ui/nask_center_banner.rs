@@ -1,5 +1,6 @@
 use crate::ui::app_ui_state::AppUIState;
 use crate::ui::renderable_trait::Renderable;
+use crate::ui::theme::Glyphs;
 use ratatui::widgets::Paragraph;
 use ratatui::widgets::Widget;
 use ratatui::{
@@ -10,38 +11,43 @@ use ratatui::{
 use std::sync::OnceLock;
 
 const GAP: u16 = 2;
-const ASCII_ART_NASK_BANNER: &str = include_str!("../../assets/nask.txt");
 static BANNER_HEIGHT: OnceLock<u16> = OnceLock::new();
 
+// the same height for both banners keeps the layout independent of the glyph set
 pub fn banner_height() -> u16 {
-    *BANNER_HEIGHT.get_or_init(|| ASCII_ART_NASK_BANNER.lines().count() as u16 + GAP)
+    *BANNER_HEIGHT.get_or_init(|| {
+        [Glyphs::UNICODE.banner, Glyphs::ASCII.banner]
+            .iter()
+            .map(|banner| banner.lines().count() as u16)
+            .max()
+            .unwrap_or(0)
+            + GAP
+    })
 }
 
 pub struct Banner;
-impl Banner {
-    fn new() -> Self {
-        Self {}
-    }
-}
 
-pub fn create_banner() -> Box<dyn Renderable> {
-    Box::new(Banner::new())
+pub fn create_banner() -> &'static dyn Renderable {
+    static BANNER: Banner = Banner;
+    &BANNER
 }
 
 impl Renderable for Banner {
     fn area_rect(&self, area: Rect) -> Rect {
         Rect {
-            x: area.x,
-            y: area.y,
-            width: area.width,
-            height: banner_height(),
+            height: banner_height().min(area.height),
+            ..area
         }
     }
 
-    fn render(&self, area: Rect, buf: &mut Buffer, _state: &mut AppUIState) {
-        let banner = Paragraph::new(ASCII_ART_NASK_BANNER)
+    fn render(&self, area: Rect, buf: &mut Buffer, state: &mut AppUIState) {
+        let banner = Paragraph::new(state.theme.glyphs.banner)
             .alignment(Alignment::Center)
-            .style(Style::default().add_modifier(Modifier::BOLD));
+            .style(
+                Style::default()
+                    .fg(state.theme.assistant_message)
+                    .add_modifier(Modifier::BOLD),
+            );
 
         banner.render(area, buf);
     }
@@ -1,3 +1,6 @@
+use std::{path::Path, time::Instant};
+
+use crossterm::event::{Event, KeyCode, KeyEvent};
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -5,12 +8,27 @@ use ratatui::{
     text::{Line, Span},
     widgets::{Block, Borders, Padding, Paragraph, Widget},
 };
+use tui_input::{Input, InputRequest, backend::crossterm::EventHandler};
 
-use crate::ui::{
-    app_ui_state::{AppUIState, Focus, InputMode, NaskInputBoxState},
-    common::{ACCENT_COLOR, SEMI_ACCENT_COLOR},
-    nask_center_banner::banner_height,
-    renderable_trait::Renderable,
+use crate::{
+    back_logic::{
+        message_loop::{Command, ContextAttachment},
+        project_files::read_attachable,
+    },
+    ui::{
+        app_ui_state::{
+            AppUIState, ChatMessage, ContextSource, Focus, InputMode, NaskInputBoxState,
+        },
+        chat_search::ChatSearch,
+        completion::{accept_completion, mentioned_paths, refresh_completion},
+        event_system::{EventSignal, KeyOperationEvent},
+        layout::INPUT_HEIGHT,
+        prompt_history::HistorySearch,
+        renderable_trait::Renderable,
+        slash_commands::run_slash_command,
+        theme::Theme,
+        vim_motions::{self, VimOutcome},
+    },
 };
 
 pub struct NaskInputBox {
@@ -42,32 +60,53 @@ pub fn clamp_input_scroll(state: &mut NaskInputBoxState) {
 }
 
 impl NaskInputBox {
-    fn new(line_height: u16) -> Self {
+    const fn new(line_height: u16) -> Self {
         Self { line_height }
     }
 
-    pub fn input_block(input_state: &NaskInputBoxState) -> Block<'static> {
-        let focused = input_state.focus == Focus::Input;
+    pub fn input_block(
+        input_state: &NaskInputBoxState,
+        focused: bool,
+        chat_search: Option<&ChatSearch>,
+        theme: &Theme,
+    ) -> Block<'static> {
         let input_mode = input_state.mode;
         let border_style = if focused && input_mode == InputMode::Insert {
             Style::default()
-                .fg(ACCENT_COLOR)
+                .fg(theme.accent)
                 .add_modifier(Modifier::BOLD)
         } else if focused && input_mode == InputMode::Normal {
-            Style::default().fg(SEMI_ACCENT_COLOR)
+            Style::default().fg(theme.semi_accent)
         } else {
-            Style::default()
+            Style::default().fg(theme.border)
         };
 
-        let title = if input_mode == InputMode::Insert {
-            "[Ask]"
-        } else {
-            ""
+        let title = match (&input_state.history_search, chat_search) {
+            (Some(search), _) if search.match_idx.is_none() && !search.query.is_empty() => {
+                format!("(failed reverse-i-search)`{}'", search.query)
+            }
+            (Some(search), _) => format!("(reverse-i-search)`{}'", search.query),
+            (None, Some(search)) if search.editing => {
+                format!("{}{}", search.prompt_prefix(), search.query)
+            }
+            (None, Some(search)) => match search.current {
+                Some(idx) => format!(
+                    "{}{} [{}/{}]",
+                    search.prompt_prefix(),
+                    search.query,
+                    idx + 1,
+                    search.hits.len()
+                ),
+                None => format!("{}{} [no match]", search.prompt_prefix(), search.query),
+            },
+            (None, None) if input_mode == InputMode::Insert => String::from("[Ask]"),
+            (None, None) => String::new(),
         };
 
         Block::default()
             .title(title)
             .borders(Borders::ALL)
+            .border_set(theme.glyphs.border)
             .border_style(border_style)
             .padding(Padding {
                 left: 1,
@@ -86,8 +125,203 @@ impl NaskInputBox {
     }
 }
 
-pub fn create_input_box(line_height: u16) -> Box<dyn Renderable> {
-    Box::new(NaskInputBox::new(line_height))
+/// Character index under `column` when clicking into the input box drawn at `area`.
+pub fn cursor_for_column(state: &NaskInputBoxState, area: Rect, column: u16) -> usize {
+    // only the block's geometry matters here, not its colors
+    let inner = NaskInputBox::input_block(state, true, None, &Theme::default()).inner(area);
+    let len = state.input.value().chars().count();
+    let offset = column.saturating_sub(inner.x) + state.input_scroll;
+    (offset as usize).min(len)
+}
+
+fn apply_vim_outcome(state: &mut AppUIState, outcome: VimOutcome) {
+    if outcome == VimOutcome::EnterInsert {
+        state.input_box_state.mode = InputMode::Insert;
+    }
+    clamp_input_scroll(&mut state.input_box_state);
+}
+
+// re-runs the Ctrl-R search and previews the hit in the input, like readline does
+pub fn update_history_search(input_box_state: &mut NaskInputBoxState, older: bool) {
+    let Some(search) = input_box_state.history_search.as_mut() else {
+        return;
+    };
+
+    let start = match (search.match_idx, older) {
+        (Some(0), true) => return,
+        (Some(idx), true) => idx - 1,
+        (Some(idx), false) => idx,
+        (None, _) => usize::MAX,
+    };
+
+    if let Some(idx) = input_box_state
+        .history
+        .search_backward(&search.query, start)
+    {
+        search.match_idx = Some(idx);
+        if let Some(entry) = input_box_state.history.get(idx) {
+            input_box_state.input = Input::new(entry.to_string());
+        }
+    } else if !older {
+        search.match_idx = None;
+    }
+    clamp_input_scroll(input_box_state);
+}
+
+pub fn insert_text(input_box_state: &mut NaskInputBoxState, text: &str) {
+    let cursor = input_box_state.input.cursor();
+    let value = input_box_state.input.value();
+    let split = value
+        .char_indices()
+        .nth(cursor)
+        .map(|(i, _)| i)
+        .unwrap_or(value.len());
+
+    let new_value = format!("{}{}{}", &value[..split], text, &value[split..]);
+    let new_cursor = cursor + text.chars().count();
+    input_box_state.input = std::mem::take(&mut input_box_state.input)
+        .with_value(new_value)
+        .with_cursor(new_cursor);
+    clamp_input_scroll(input_box_state);
+}
+
+// `@path` mentions are read when the prompt is sent, an open buffer wins over the file on disk.
+// Only attached entries and project files count, anything else (`@someone`, `@/etc/passwd`)
+// stays plain text. Files over the size limit are reported and left out.
+fn mention_attachments(
+    state: &mut AppUIState,
+    prompt: &str,
+    attached: &[ContextAttachment],
+) -> Vec<ContextAttachment> {
+    let files = state.project_files();
+    let mut attachments = Vec::new();
+    for path in mentioned_paths(prompt) {
+        if attached.iter().any(|a| a.label == path) {
+            continue;
+        }
+        let buffer = state
+            .additional_context_state
+            .entries
+            .iter()
+            .find(|e| e.entry == path)
+            .and_then(|e| e.content.clone());
+        let content = match buffer {
+            Some(content) => content,
+            // the walk may be older than the file, or stop short of it in a huge tree
+            None if files.binary_search_by(|f| f.as_str().cmp(path)).is_ok()
+                || Path::new(path).is_file() =>
+            {
+                match read_attachable(path) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        state
+                            .chat_state
+                            .chat_messages
+                            .push(ChatMessage::error(format!("@{}: {:#}", path, e)));
+                        continue;
+                    }
+                }
+            }
+            None => continue,
+        };
+        attachments.push(ContextAttachment {
+            label: path.to_string(),
+            content,
+        });
+    }
+    attachments
+}
+
+/// Sends `prompt` with the checked contexts. Git, cargo and tmux contexts are refreshed
+/// off the UI thread first, the prompt waits in `pending_prompts` until they are back.
+pub fn send_prompt(state: &mut AppUIState, prompt: String) {
+    state.chat_state.pending_prompts.push(prompt);
+    state.chat_state.scroll_offset = 0;
+    let context_state = &mut state.additional_context_state;
+    let fresh = std::mem::take(&mut context_state.fresh);
+    if context_state.refreshing {
+        return; // goes out with the refresh already running
+    }
+    let sources: Vec<ContextSource> = context_state
+        .computed_sources()
+        .into_iter()
+        .filter(|source| !fresh.contains(source))
+        .collect();
+    if sources.is_empty() {
+        send_pending_prompts(state);
+        return;
+    }
+    context_state.refreshing = true;
+    let cargo_built = context_state.cargo_built.clone();
+    (state.pump_message_loop)(Command::RefreshContexts {
+        sources,
+        cargo_built,
+    });
+}
+
+pub fn send_pending_prompts(state: &mut AppUIState) {
+    for prompt in std::mem::take(&mut state.chat_state.pending_prompts) {
+        let mut contexts: Vec<ContextAttachment> = state
+            .additional_context_state
+            .entries
+            .iter()
+            .filter(|e| e.checked)
+            .filter_map(|e| {
+                e.content.as_ref().map(|content| ContextAttachment {
+                    label: e.entry.clone(),
+                    content: content.clone(),
+                })
+            })
+            .collect();
+        let mentions = mention_attachments(state, &prompt, &contexts);
+        contexts.extend(mentions);
+        // in flight from now on, not only once GenerationStarted comes back
+        state.generation.started = Some(Instant::now());
+        (state.pump_message_loop)(Command::ChatMessage {
+            prompt,
+            contexts,
+            model: state.meta_info_state.model_name.clone(),
+            system: state.chat_state.system_prompt.clone(),
+        });
+    }
+}
+
+pub fn submit_input(state: &mut AppUIState) -> EventSignal {
+    let prompt = String::from(state.input_box_state.input.value());
+    if prompt.trim().is_empty() {
+        return EventSignal::Continue;
+    }
+    state.input_box_state.history.push(&prompt);
+    state.input_box_state.input.reset();
+    state.input_box_state.completion = None;
+    clamp_input_scroll(&mut state.input_box_state);
+
+    // `/cmd` runs locally, `//text` sends `/text`, `!cmd` is short for `/run cmd`
+    let prompt = match prompt.strip_prefix('/') {
+        Some(rest) if rest.starts_with('/') => rest.to_string(),
+        Some(_) => return run_slash_command(state, &prompt),
+        None => prompt,
+    };
+    if let Some(command) = prompt.strip_prefix('!') {
+        return run_slash_command(state, &format!("/run {}", command));
+    }
+
+    let chat_state = &mut state.chat_state;
+    if let Some(idx) = chat_state.resend_from.take() {
+        chat_state.chat_messages.truncate(idx);
+        chat_state.search = None;
+    }
+    chat_state
+        .chat_messages
+        .push(ChatMessage::prompt(prompt.clone()));
+
+    send_prompt(state, prompt);
+    EventSignal::Continue
+}
+
+pub fn create_input_box() -> &'static dyn Renderable {
+    static INPUT: NaskInputBox = NaskInputBox::new(INPUT_HEIGHT);
+    &INPUT
 }
 
 impl Renderable for NaskInputBox {
@@ -102,16 +336,25 @@ impl Renderable for NaskInputBox {
     }
 
     fn render(&self, area: Rect, buf: &mut Buffer, state: &mut AppUIState) {
+        let focused = state.focus == Focus::Input;
         let input_box_state = &mut state.input_box_state;
 
-        let block = Self::input_block(input_box_state);
+        let block = Self::input_block(
+            input_box_state,
+            focused,
+            state.chat_state.search.as_ref(),
+            &state.theme,
+        );
         let inner = block.inner(area);
 
         input_box_state.last_input_inner_width = inner.width;
         clamp_input_scroll(input_box_state);
 
         let inner_w = inner.width as usize;
-        let value = input_box_state.input.value();
+        // multi-line prompts (editor, paste) are shown on one line with a visible break marker
+        let newline = state.theme.glyphs.newline.to_string();
+        let value = input_box_state.input.value().replace('\n', &newline);
+        let value = value.as_str();
         let visible = if value.is_empty() {
             Line::from(Span::styled(
                 "Feel free to ask a question (:",
@@ -119,13 +362,26 @@ impl Renderable for NaskInputBox {
             ))
         } else if inner_w == 0 {
             Line::from("")
+        } else if input_box_state.mode == InputMode::Normal {
+            // no terminal cursor in normal mode, draw a block cursor instead
+            let start = input_box_state.input_scroll as usize;
+            let cursor = input_box_state.input.cursor().saturating_sub(start);
+            let s: Vec<char> = value.chars().skip(start).take(inner_w).collect();
+            let before: String = s.iter().take(cursor).collect();
+            let under: String = s.get(cursor).map(|c| c.to_string()).unwrap_or_default();
+            let after: String = s.iter().skip(cursor + 1).collect();
+            Line::from(vec![
+                Span::raw(before),
+                Span::styled(under, Style::default().add_modifier(Modifier::REVERSED)),
+                Span::raw(after),
+            ])
         } else {
             let start = input_box_state.input_scroll as usize;
-            let s: String = value.chars().skip(start).take(inner_w as usize).collect();
+            let s: String = value.chars().skip(start).take(inner_w).collect();
             Line::from(s)
         };
 
-        if input_box_state.mode != InputMode::Insert {
+        if input_box_state.mode != InputMode::Insert || !focused {
             input_box_state.cursor_pos = None;
         } else {
             input_box_state.cursor_pos = Some(self.cursor_pos(inner, input_box_state));
@@ -133,4 +389,246 @@ impl Renderable for NaskInputBox {
 
         Paragraph::new(visible).block(block).render(area, buf);
     }
+    fn handle_key_operation(
+        &self,
+        key_operation_event: KeyOperationEvent,
+        key: KeyEvent,
+        state: &mut AppUIState,
+    ) -> Option<EventSignal> {
+        match key_operation_event {
+            KeyOperationEvent::InputChangeToInsertMode => {
+                state.input_box_state.vim.reset();
+                state.input_box_state.mode = InputMode::Insert
+            }
+            KeyOperationEvent::InputChangeToNormalMode => {
+                state.input_box_state.completion = None;
+                vim_motions::enter_normal_mode(&mut state.input_box_state);
+                state.input_box_state.mode = InputMode::Normal
+            }
+            KeyOperationEvent::InputAppend => {
+                state.input_box_state.vim.reset();
+                state
+                    .input_box_state
+                    .input
+                    .handle(InputRequest::GoToNextChar);
+                state.input_box_state.mode = InputMode::Insert
+            }
+            // the prompt is a single line, so opening a new line means continuing at its end
+            KeyOperationEvent::InputAppendAtEnd | KeyOperationEvent::InputOpenLine => {
+                state.input_box_state.vim.reset();
+                state.input_box_state.input.handle(InputRequest::GoToEnd);
+                state.input_box_state.mode = InputMode::Insert
+            }
+            KeyOperationEvent::InputInsertAtStart => {
+                state.input_box_state.vim.reset();
+                state.input_box_state.input.handle(InputRequest::GoToStart);
+                state.input_box_state.mode = InputMode::Insert
+            }
+            KeyOperationEvent::VimMotion(motion) => {
+                let outcome = vim_motions::handle_motion(&mut state.input_box_state, motion);
+                apply_vim_outcome(state, outcome);
+            }
+            KeyOperationEvent::VimOperator(op) => {
+                let outcome = vim_motions::handle_operator(&mut state.input_box_state, op);
+                apply_vim_outcome(state, outcome);
+            }
+            KeyOperationEvent::VimTextObject(object) => {
+                let outcome = vim_motions::handle_text_object(&mut state.input_box_state, object);
+                apply_vim_outcome(state, outcome);
+            }
+            KeyOperationEvent::VimCount(digit) => {
+                state.input_box_state.vim.push_count(digit);
+            }
+            KeyOperationEvent::VimDeleteChar => {
+                vim_motions::delete_char(&mut state.input_box_state);
+                clamp_input_scroll(&mut state.input_box_state);
+            }
+            KeyOperationEvent::VimPutAfter => {
+                vim_motions::put(&mut state.input_box_state, false);
+                clamp_input_scroll(&mut state.input_box_state);
+            }
+            KeyOperationEvent::VimPutBefore => {
+                vim_motions::put(&mut state.input_box_state, true);
+                clamp_input_scroll(&mut state.input_box_state);
+            }
+            KeyOperationEvent::VimCancel => {
+                state.input_box_state.vim.reset();
+            }
+            KeyOperationEvent::InputSubmitted => return Some(submit_input(state)),
+            KeyOperationEvent::HistoryOlder => {
+                let input_box_state = &mut state.input_box_state;
+                let current = input_box_state.input.value().to_string();
+                if let Some(entry) = input_box_state.history.older(&current) {
+                    input_box_state.input = Input::new(entry.to_string());
+                    clamp_input_scroll(input_box_state);
+                }
+            }
+            KeyOperationEvent::HistoryNewer => {
+                let input_box_state = &mut state.input_box_state;
+                if let Some(entry) = input_box_state.history.newer() {
+                    input_box_state.input = Input::new(entry);
+                    clamp_input_scroll(input_box_state);
+                }
+            }
+            KeyOperationEvent::HistorySearchStart => {
+                state.input_box_state.history_search = Some(HistorySearch {
+                    query: String::new(),
+                    match_idx: None,
+                    draft: state.input_box_state.input.value().to_string(),
+                });
+            }
+            KeyOperationEvent::HistorySearchOlder => {
+                update_history_search(&mut state.input_box_state, true);
+            }
+            KeyOperationEvent::HistorySearchBackspace => {
+                if let Some(search) = state.input_box_state.history_search.as_mut() {
+                    search.query.pop();
+                    search.match_idx = None;
+                }
+                update_history_search(&mut state.input_box_state, false);
+            }
+            KeyOperationEvent::ForwardToHistorySearch => {
+                if let (KeyCode::Char(c), Some(search)) =
+                    (key.code, state.input_box_state.history_search.as_mut())
+                {
+                    search.query.push(c);
+                }
+                update_history_search(&mut state.input_box_state, false);
+            }
+            KeyOperationEvent::HistorySearchAccept => {
+                state.input_box_state.history_search = None;
+            }
+            KeyOperationEvent::HistorySearchCancel => {
+                if let Some(search) = state.input_box_state.history_search.take() {
+                    state.input_box_state.input = Input::new(search.draft);
+                    clamp_input_scroll(&mut state.input_box_state);
+                }
+            }
+            KeyOperationEvent::ForwardToInput => {
+                EventHandler::handle_event(&mut state.input_box_state.input, &Event::Key(key));
+                clamp_input_scroll(&mut state.input_box_state);
+                refresh_completion(state);
+            }
+            KeyOperationEvent::CompletionNext | KeyOperationEvent::CompletionPrev => {
+                if let Some(completion) = state.input_box_state.completion.as_mut() {
+                    completion.step(key_operation_event == KeyOperationEvent::CompletionPrev);
+                }
+            }
+            KeyOperationEvent::CompletionAccept => accept_completion(state),
+            KeyOperationEvent::CompletionDismiss => state.input_box_state.completion = None,
+            _ => return None,
+        }
+        Some(EventSignal::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    fn normal_mode(value: &str, cursor: usize) -> AppUIState {
+        let mut state = AppUIState::new(|_| {});
+        state.input_box_state.input = Input::new(value.to_string()).with_cursor(cursor);
+        state.input_box_state.mode = InputMode::Normal;
+        state
+    }
+
+    fn press(state: &mut AppUIState, event: KeyOperationEvent) {
+        let key = KeyEvent::from(KeyCode::Null);
+        create_input_box().handle_key_operation(event, key, state);
+    }
+
+    #[test]
+    fn insert_mode_entries() {
+        for (event, cursor) in [
+            (KeyOperationEvent::InputAppend, 2),
+            (KeyOperationEvent::InputAppendAtEnd, 3),
+            (KeyOperationEvent::InputInsertAtStart, 0),
+            (KeyOperationEvent::InputOpenLine, 3),
+        ] {
+            let mut state = normal_mode("abc", 1);
+            state.input_box_state.vim.push_count(2);
+            press(&mut state, event);
+            assert!(
+                state.input_box_state.mode == InputMode::Insert,
+                "{:?}",
+                event
+            );
+            assert_eq!(state.input_box_state.input.cursor(), cursor, "{:?}", event);
+            assert_eq!(state.input_box_state.vim.count, None, "{:?}", event);
+        }
+    }
+
+    // submits `line` and returns what reached the message loop
+    fn submit(state: &mut AppUIState, line: &str) -> Vec<Command> {
+        let sent = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&sent);
+        state.pump_message_loop = Box::new(move |cmd| sink.borrow_mut().push(cmd));
+        state.project_files.walked = Some(Instant::now()); // no walk for the mentions
+        state.input_box_state.input = Input::new(line.to_string());
+        submit_input(state);
+        sent.take()
+    }
+
+    fn last_message(state: &AppUIState) -> &ChatMessage {
+        state.chat_state.chat_messages.last().unwrap()
+    }
+
+    #[test]
+    fn slash_command_runs_locally() {
+        let mut state = AppUIState::new(|_| {});
+        let sent = submit(&mut state, "/model  some-model ");
+        assert!(sent.is_empty());
+        assert!(state.chat_state.chat_messages.is_empty());
+        assert_eq!(state.meta_info_state.model_name, "some-model");
+    }
+
+    #[test]
+    fn unknown_slash_command_is_reported() {
+        let mut state = AppUIState::new(|_| {});
+        let sent = submit(&mut state, "/nope arg");
+        assert!(sent.is_empty());
+        assert!(last_message(&state).is_error);
+        assert!(last_message(&state).message.contains("`/nope`"));
+    }
+
+    #[test]
+    fn double_slash_sends_a_literal_slash() {
+        let mut state = AppUIState::new(|_| {});
+        let sent = submit(&mut state, "//etc/hosts looks odd");
+        assert!(matches!(
+            &sent[..],
+            [Command::ChatMessage { prompt, .. }] if prompt == "/etc/hosts looks odd"
+        ));
+        assert_eq!(last_message(&state).message, "/etc/hosts looks odd");
+    }
+
+    #[test]
+    fn bang_runs_a_shell_command() {
+        let mut state = AppUIState::new(|_| {});
+        let sent = submit(&mut state, "!ls -a");
+        assert!(matches!(
+            &sent[..],
+            [Command::RunShell { command }] if command == "ls -a"
+        ));
+        assert!(state.chat_state.chat_messages.is_empty());
+    }
+
+    #[test]
+    fn clear_and_load_wait_for_the_answer() {
+        for line in ["/clear", "/load some-name"] {
+            let mut state = AppUIState::new(|_| {});
+            submit(&mut state, "question");
+            assert!(state.prompt_in_flight());
+            submit(&mut state, line);
+            assert_eq!(state.chat_state.chat_messages.len(), 2, "{}", line);
+            assert!(
+                last_message(&state).message.contains("on its way"),
+                "{}",
+                line
+            );
+        }
+    }
 }
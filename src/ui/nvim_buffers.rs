@@ -1,53 +1,44 @@
+use std::env;
+use std::path::Path;
+
 use crate::ui::app_ui_state::AdditionalContextState;
 use crate::ui::app_ui_state::AppUIState;
-use crate::ui::common::ACCENT_COLOR;
+use crate::ui::app_ui_state::CheckBoxEntry;
+use crate::ui::app_ui_state::ContextFilter;
+use crate::ui::app_ui_state::Focus;
+use crate::ui::event_system::{EventSignal, KeyOperationEvent};
+use crate::ui::layout::is_narrow;
+use crate::ui::list_view::scroll_into_view;
 use crate::ui::renderable_trait::Renderable;
-use ratatui::style::Color;
+use crate::ui::theme::Theme;
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::text::Line;
 use ratatui::text::Span;
 use ratatui::widgets::Paragraph;
 use ratatui::widgets::Widget;
+use ratatui::widgets::{Block, Borders, Cell, Row, Table};
 use ratatui::{
     buffer::Buffer,
-    layout::{Alignment, Rect},
+    layout::{Alignment, Constraint, Rect},
     style::{Modifier, Style},
 };
 
 pub struct NvimBuffers;
 
 impl NvimBuffers {
-    fn render_collapsed(area: Rect, count: usize, buf: &mut Buffer) {
+    fn render_collapsed(area: Rect, count: usize, theme: &Theme, buf: &mut Buffer) {
+        let dim = Style::default().fg(theme.dim).add_modifier(Modifier::DIM);
         let line = Line::from(vec![
-            Span::styled(
-                "▴",
-                Style::default()
-                    .fg(Color::DarkGray)
-                    .add_modifier(Modifier::DIM),
-            ),
-            Span::styled(
-                "[",
-                Style::default()
-                    .fg(Color::DarkGray)
-                    .add_modifier(Modifier::DIM),
-            ),
+            Span::styled(theme.glyphs.collapsed, dim),
+            Span::styled("[", dim),
             Span::styled(
                 count.to_string(),
                 Style::default()
-                    .fg(ACCENT_COLOR)
+                    .fg(theme.accent)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::styled(
-                "]",
-                Style::default()
-                    .fg(Color::DarkGray)
-                    .add_modifier(Modifier::DIM),
-            ),
-            Span::styled(
-                "▴",
-                Style::default()
-                    .fg(Color::DarkGray)
-                    .add_modifier(Modifier::DIM),
-            ),
+            Span::styled("]", dim),
+            Span::styled(theme.glyphs.collapsed, dim),
         ]);
 
         Paragraph::new(line)
@@ -57,51 +48,146 @@ impl NvimBuffers {
 
     fn render_expanded(
         area: Rect,
-        additional_context_state: &AdditionalContextState,
+        additional_context_state: &mut AdditionalContextState,
+        focused: bool,
+        theme: &Theme,
         buf: &mut Buffer,
     ) {
-        if additional_context_state.entries.is_empty() {
-            Paragraph::new("No entries...")
-                .alignment(Alignment::Center)
-                .style(Style::default().bg(Color::Rgb(22, 22, 22)))
-                .render(area, buf);
-            return;
+        let border_style = if focused {
+            Style::default().fg(theme.accent)
+        } else {
+            Style::default().fg(theme.border)
+        };
+        let attached = additional_context_state
+            .entries
+            .iter()
+            .filter(|e| e.checked)
+            .count();
+        let mut block = Block::default()
+            .title(format!(
+                " Contexts {}/{} ",
+                attached,
+                additional_context_state.entries.len()
+            ))
+            .borders(Borders::ALL)
+            .border_set(theme.glyphs.border)
+            .border_style(border_style)
+            .style(Style::default().bg(theme.surface));
+        if let Some(filter) = &additional_context_state.filter {
+            let cursor = if filter.editing { "_" } else { "" };
+            block = block.title_bottom(format!(" /{}{} ", filter.query, cursor));
         }
 
-        let mut spans: Vec<Span> = Vec::new();
-        let len = additional_context_state.entries.len();
-        for (idx, entry) in additional_context_state.entries.iter().enumerate() {
-            let is_checked = entry.checked;
-            let is_selected = entry.selected;
+        let inner = block.inner(area);
+        block.render(area, buf);
 
-            let text_style = if is_selected {
-                Style::default().fg(ACCENT_COLOR).bold()
+        let visible = additional_context_state.visible();
+        if visible.is_empty() {
+            let text = if additional_context_state.entries.is_empty() {
+                "No entries..."
             } else {
-                Style::default()
+                "No matches"
             };
+            Paragraph::new(Span::styled(text, Style::default().fg(theme.dim)))
+                .alignment(Alignment::Center)
+                .render(inner, buf);
+            return;
+        }
 
-            spans.push(Span::raw("["));
+        // keep the selected row inside the window
+        let height = inner.height as usize;
+        let selected_row = additional_context_state
+            .selected()
+            .and_then(|sel| visible.iter().position(|idx| *idx == sel))
+            .unwrap_or(0);
+        let scroll = &mut additional_context_state.scroll;
+        *scroll = scroll_into_view(
+            (*scroll).min(visible.len().saturating_sub(height)),
+            selected_row,
+            height,
+        );
 
-            if is_checked {
-                spans.push(Span::styled("x", Style::default().fg(ACCENT_COLOR).bold()));
-            } else {
-                spans.push(Span::raw(" "));
-            }
+        let narrow = is_narrow(area);
+        let rows = visible.iter().skip(*scroll).take(height).map(|idx| {
+            entry_row(
+                &additional_context_state.entries[*idx],
+                focused,
+                narrow,
+                theme,
+            )
+        });
 
-            spans.push(Span::raw("] "));
-            spans.push(Span::styled(entry.entry.as_str(), text_style));
-            if len - 1 != idx {
-                spans.push(Span::raw("  -   "));
-            }
-        }
+        let widths: &[Constraint] = if narrow {
+            &[Constraint::Length(3), Constraint::Fill(1)]
+        } else {
+            &[
+                Constraint::Length(3),
+                Constraint::Fill(1),
+                Constraint::Length(11),
+                Constraint::Length(11),
+                Constraint::Length(5),
+            ]
+        };
+        Table::new(rows, widths).render(inner, buf);
+    }
+}
 
-        let line = Line::from(spans);
+fn entry_row<'a>(entry: &'a CheckBoxEntry, focused: bool, narrow: bool, theme: &Theme) -> Row<'a> {
+    let check = if entry.checked {
+        Span::styled("x", Style::default().fg(theme.accent).bold())
+    } else {
+        Span::raw(" ")
+    };
+    let check = Line::from(vec![Span::raw("["), check, Span::raw("]")]);
 
-        Paragraph::new(line)
-            .style(Style::default().bg(Color::Rgb(22, 22, 22)))
-            .alignment(Alignment::Center)
-            .render(area, buf);
+    let mut cells = vec![Cell::from(check), Cell::from(display_path(&entry.entry))];
+    if !narrow {
+        let dim = Style::default().fg(theme.dim);
+        let lines = entry
+            .line_count()
+            .map(|n| format!("{} lines", n))
+            .unwrap_or_default();
+        let tokens = entry
+            .estimated_tokens()
+            .map(|n| format!("~{} tok", n))
+            .unwrap_or_default();
+        cells.push(Cell::from(Line::from(lines).right_aligned()).style(dim));
+        cells.push(Cell::from(Line::from(tokens).right_aligned()).style(dim));
+        cells.push(Cell::from(entry.source.label()).style(dim));
     }
+
+    let style = if entry.selected && focused {
+        theme.selection_style().fg(theme.accent).bold()
+    } else if entry.selected {
+        Style::default().fg(theme.accent).bold()
+    } else {
+        Style::default()
+    };
+    Row::new(cells).style(style)
+}
+
+// paths below the working directory are shown relative to it
+fn display_path(entry: &str) -> String {
+    let path = Path::new(entry);
+    env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok())
+        .map(|rel| rel.display().to_string())
+        .unwrap_or_else(|| entry.to_string())
+}
+
+/// Index of the entry drawn at `row` by the expanded panel.
+pub fn entry_at_row(
+    additional_context_state: &AdditionalContextState,
+    area: Rect,
+    row: u16,
+) -> Option<usize> {
+    let inner = Block::default().borders(Borders::ALL).inner(area);
+    if !(inner.y..inner.bottom()).contains(&row) {
+        return None;
+    }
+    let offset = additional_context_state.scroll + (row - inner.y) as usize;
+    additional_context_state.visible().get(offset).copied()
 }
 
 pub fn create_nvim_buffers() -> &'static dyn Renderable {
@@ -109,26 +195,74 @@ pub fn create_nvim_buffers() -> &'static dyn Renderable {
     &NVIM
 }
 
-const NVIM_BUFFERS_HEIGHT: u16 = 1;
-
 impl Renderable for NvimBuffers {
     fn area_rect(&self, area: Rect) -> Rect {
-        Rect {
-            x: area.x,
-            y: area.y + area.height.saturating_sub(NVIM_BUFFERS_HEIGHT),
-            width: area.width,
-            height: NVIM_BUFFERS_HEIGHT,
-        }
+        area
     }
 
     fn render(&self, area: Rect, buf: &mut Buffer, state: &mut AppUIState) {
-        let buffer_state = &state.additional_context_state;
+        let focused = state.focus == Focus::Contexts;
+        let buffer_state = &mut state.additional_context_state;
 
         let count = buffer_state.entries.len();
         if buffer_state.collapsed {
-            NvimBuffers::render_collapsed(area, count, buf);
+            NvimBuffers::render_collapsed(area, count, &state.theme, buf);
         } else {
-            NvimBuffers::render_expanded(area, buffer_state, buf);
+            NvimBuffers::render_expanded(area, buffer_state, focused, &state.theme, buf);
+        }
+    }
+    fn handle_key_operation(
+        &self,
+        key_operation_event: KeyOperationEvent,
+        key: KeyEvent,
+        state: &mut AppUIState,
+    ) -> Option<EventSignal> {
+        let context_state = &mut state.additional_context_state;
+        match key_operation_event {
+            KeyOperationEvent::ToggleBuffers => {
+                context_state.collapsed = !context_state.collapsed;
+                if context_state.collapsed && state.focus == Focus::Contexts {
+                    state.set_focus(Focus::Input);
+                }
+            }
+            KeyOperationEvent::CheckSelectedBuffer => {
+                if let Some(item) = context_state.entries.iter_mut().find(|v| v.selected) {
+                    item.checked = !item.checked;
+                }
+            }
+            KeyOperationEvent::SelectPrevBuffer => context_state.step_selection(true),
+            KeyOperationEvent::SelectNextBuffer => context_state.step_selection(false),
+            KeyOperationEvent::ContextFilterStart => {
+                context_state.filter = Some(ContextFilter {
+                    query: String::new(),
+                    editing: true,
+                });
+                context_state.refilter();
+            }
+            KeyOperationEvent::ForwardToContextFilter
+            | KeyOperationEvent::ContextFilterBackspace => {
+                let filter = context_state.filter.as_mut()?;
+                match key.code {
+                    KeyCode::Char(c) => filter.query.push(c),
+                    _ => {
+                        filter.query.pop();
+                    }
+                }
+                context_state.refilter();
+            }
+            KeyOperationEvent::ContextFilterConfirm => {
+                let filter = context_state.filter.as_mut()?;
+                filter.editing = false;
+                if filter.query.is_empty() {
+                    context_state.filter = None;
+                }
+            }
+            KeyOperationEvent::ContextFilterCancel => {
+                context_state.filter = None;
+                context_state.refilter();
+            }
+            _ => return None,
         }
+        Some(EventSignal::Continue)
     }
 }
@@ -0,0 +1,226 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::{
+    back_logic::message_loop::Command,
+    ui::{app_ui_state::CheckBoxEntry, event_system::KeyOperationEvent, theme::Theme},
+};
+
+pub enum OverlayOutcome {
+    Consumed,
+    Close,
+    /// Closes the overlay and runs the operation as if its key had been pressed.
+    CloseWith(KeyOperationEvent),
+    /// Closes the overlay and leaves the text in the prompt for the user to finish.
+    CloseWithInput(String),
+    /// Closes the overlay and attaches the entries to the context panel.
+    CloseWithContexts(Vec<CheckBoxEntry>),
+    /// Closes the overlay and hands the command to the message loop.
+    CloseWithCommand(Command),
+}
+
+/// A layer drawn above the components. Unlike a `Renderable` it owns its state,
+/// the stack hands it the keys while it is the topmost modal layer.
+pub trait Overlay {
+    fn area_rect(&self, frame: Rect) -> Rect;
+    fn render(&mut self, area: Rect, buf: &mut Buffer, theme: &Theme);
+    fn handle_key(&mut self, key: KeyEvent) -> OverlayOutcome;
+
+    /// Pasted text, overlays without a text field drop it.
+    fn handle_paste(&mut self, _text: &str) {}
+
+    /// Modal overlays take all keys, the others (toasts) only draw.
+    fn is_modal(&self) -> bool {
+        true
+    }
+
+    fn is_expired(&self) -> bool {
+        false
+    }
+}
+
+/// Overlays in z-order, the last one is drawn on top and gets the keys first.
+#[derive(Default)]
+pub struct OverlayStack {
+    layers: Vec<Box<dyn Overlay>>,
+}
+
+impl OverlayStack {
+    pub fn push(&mut self, overlay: impl Overlay + 'static) {
+        self.layers.push(Box::new(overlay));
+    }
+
+    pub fn has_modal(&self) -> bool {
+        self.layers.iter().any(|layer| layer.is_modal())
+    }
+
+    // toasts time out on their own and need a redraw for it
+    pub fn needs_tick(&self) -> bool {
+        self.layers.iter().any(|layer| !layer.is_modal())
+    }
+
+    /// Offers the key to the topmost modal overlay, `None` lets it through to the components.
+    /// Esc always dismisses that overlay.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<OverlayOutcome> {
+        let idx = self.layers.iter().rposition(|layer| layer.is_modal())?;
+        let outcome = if key.code == KeyCode::Esc {
+            OverlayOutcome::Close
+        } else {
+            self.layers[idx].handle_key(key)
+        };
+        if !matches!(outcome, OverlayOutcome::Consumed) {
+            self.layers.remove(idx);
+        }
+        Some(outcome)
+    }
+
+    /// Hands pasted text to the topmost modal overlay, `false` if there is none.
+    pub fn handle_paste(&mut self, text: &str) -> bool {
+        match self.layers.iter_mut().rev().find(|layer| layer.is_modal()) {
+            Some(layer) => {
+                layer.handle_paste(text);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn render(&mut self, frame: Rect, buf: &mut Buffer, theme: &Theme) {
+        self.layers.retain(|layer| !layer.is_expired());
+        for layer in &mut self.layers {
+            let area = layer.area_rect(frame);
+            Clear.render(area, buf);
+            layer.render(area, buf, theme);
+        }
+    }
+}
+
+/// `width` x `height` in the middle of `frame`, shrunk to fit.
+pub fn centered_rect(frame: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(frame.width);
+    let height = height.min(frame.height);
+    Rect {
+        x: frame.x + (frame.width - width) / 2,
+        y: frame.y + (frame.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+const CONFIRM_WIDTH: u16 = 48;
+
+/// Yes/no question, runs `on_accept` on `y` or Enter.
+pub struct Confirm {
+    message: String,
+    on_accept: KeyOperationEvent,
+}
+
+impl Confirm {
+    pub fn new(message: impl Into<String>, on_accept: KeyOperationEvent) -> Self {
+        Self {
+            message: message.into(),
+            on_accept,
+        }
+    }
+}
+
+impl Overlay for Confirm {
+    fn area_rect(&self, frame: Rect) -> Rect {
+        // borders, the message and the hint line
+        centered_rect(frame, CONFIRM_WIDTH, 5)
+    }
+
+    fn render(&mut self, area: Rect, buf: &mut Buffer, theme: &Theme) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(theme.glyphs.border)
+            .border_style(Style::default().fg(theme.accent));
+        let lines = vec![
+            Line::from(self.message.as_str()),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("y", Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled(" yes   ", Style::default().fg(theme.dim)),
+                Span::styled("n", Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled(" no", Style::default().fg(theme.dim)),
+            ]),
+        ];
+        Paragraph::new(lines)
+            .block(block)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .style(Style::default().bg(theme.surface))
+            .render(area, buf);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> OverlayOutcome {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => OverlayOutcome::CloseWith(self.on_accept),
+            KeyCode::Char('n') | KeyCode::Char('q') => OverlayOutcome::Close,
+            _ => OverlayOutcome::Consumed,
+        }
+    }
+}
+
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+
+/// Short notice in the bottom right corner that goes away by itself.
+pub struct Toast {
+    message: String,
+    until: Instant,
+}
+
+impl Toast {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            until: Instant::now() + TOAST_DURATION,
+        }
+    }
+}
+
+impl Overlay for Toast {
+    // above the status line, clear of the context bar
+    fn area_rect(&self, frame: Rect) -> Rect {
+        let width = (Line::from(self.message.as_str()).width() as u16 + 4).min(frame.width);
+        let height = 3.min(frame.height);
+        Rect {
+            x: frame.right() - width,
+            y: frame.bottom().saturating_sub(height + 2).max(frame.y),
+            width,
+            height,
+        }
+    }
+
+    fn render(&mut self, area: Rect, buf: &mut Buffer, theme: &Theme) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(theme.glyphs.border)
+            .border_style(Style::default().fg(theme.dim));
+        Paragraph::new(self.message.as_str())
+            .block(block)
+            .alignment(Alignment::Center)
+            .style(Style::default().bg(theme.surface))
+            .render(area, buf);
+    }
+
+    fn handle_key(&mut self, _key: KeyEvent) -> OverlayOutcome {
+        OverlayOutcome::Consumed
+    }
+
+    fn is_modal(&self) -> bool {
+        false
+    }
+
+    fn is_expired(&self) -> bool {
+        Instant::now() >= self.until
+    }
+}
@@ -0,0 +1,322 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::config::app_config::{HistoryConfig, state_dir};
+
+pub struct HistorySearch {
+    pub query: String,
+    pub match_idx: Option<usize>,
+    pub draft: String,
+}
+
+#[derive(Default)]
+pub struct PromptHistory {
+    entries: Vec<String>,
+    max_size: usize,
+    dedup: bool,
+    path: Option<PathBuf>,
+    file_lines: usize, // lines in the history file as far as this session knows
+    browse_idx: Option<usize>,
+    draft: String,
+}
+
+// one history file per project, named after the escaped project path (like vim's undodir)
+fn project_history_path() -> Option<PathBuf> {
+    let project = env::current_dir().ok()?;
+    let project = project.canonicalize().unwrap_or(project);
+    let name = project
+        .to_string_lossy()
+        .replace('%', "%%")
+        .replace('/', "%");
+    Some(state_dir()?.join("history").join(name))
+}
+
+fn escape_entry(entry: &str) -> String {
+    entry.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape_entry(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+impl PromptHistory {
+    pub fn load(config: &HistoryConfig) -> Self {
+        let path = if config.persist {
+            project_history_path()
+        } else {
+            None
+        };
+        Self::load_from(config, path)
+    }
+
+    fn load_from(config: &HistoryConfig, path: Option<PathBuf>) -> Self {
+        let mut history = Self {
+            entries: Vec::new(),
+            max_size: config.size,
+            dedup: config.dedup,
+            path,
+            file_lines: 0,
+            browse_idx: None,
+            draft: String::new(),
+        };
+        history.reload();
+        history
+    }
+
+    // the file as written by every session, deduplicated and trimmed like in memory
+    fn reload(&mut self) {
+        let lines: Vec<String> = self
+            .path
+            .as_deref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|raw| raw.lines().map(unescape_entry).collect())
+            .unwrap_or_default();
+        self.file_lines = lines.len();
+        self.entries.clear();
+        for entry in lines {
+            self.add(entry);
+        }
+    }
+
+    // false for a repeat of the last entry, which is dropped even without dedup
+    fn add(&mut self, entry: String) -> bool {
+        if self.dedup {
+            self.entries.retain(|e| *e != entry);
+        } else if self.entries.last() == Some(&entry) {
+            return false;
+        }
+        self.entries.push(entry);
+        if self.entries.len() > self.max_size {
+            let excess = self.entries.len() - self.max_size;
+            self.entries.drain(..excess);
+        }
+        true
+    }
+
+    // appending keeps what other sessions wrote in the meantime, the file is only
+    // rewritten once it is half again over the limit, merged with their entries
+    fn save(&mut self, entry: &str) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        // losing history is not worth interrupting the session for
+        if Self::append_entry(&path, entry).is_err() {
+            return;
+        }
+        self.file_lines += 1;
+        if self.file_lines > self.max_size + self.max_size / 2 {
+            self.reload();
+            if Self::write_entries(&path, &self.entries).is_ok() {
+                self.file_lines = self.entries.len();
+            }
+        }
+    }
+
+    fn append_entry(path: &Path, entry: &str) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        // one write per line, so lines of concurrent sessions do not interleave
+        file.write_all(format!("{}\n", escape_entry(entry)).as_bytes())
+    }
+
+    // written next to the file and renamed over it, a crash leaves the old one intact
+    fn write_entries(path: &Path, entries: &[String]) -> std::io::Result<()> {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let mut file = tempfile::NamedTempFile::new_in(dir)?;
+        for entry in entries {
+            writeln!(file, "{}", escape_entry(entry))?;
+        }
+        file.persist(path)?;
+        Ok(())
+    }
+
+    pub fn push(&mut self, entry: &str) {
+        self.browse_idx = None;
+        self.draft.clear();
+
+        if entry.trim().is_empty() || self.max_size == 0 {
+            return;
+        }
+        if self.add(entry.to_string()) {
+            self.save(entry);
+        }
+    }
+
+    pub fn older(&mut self, current: &str) -> Option<&str> {
+        let idx = match self.browse_idx {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(idx) => idx.saturating_sub(1),
+        };
+        self.browse_idx = Some(idx);
+        self.entries.get(idx).map(String::as_str)
+    }
+
+    pub fn newer(&mut self) -> Option<String> {
+        let idx = self.browse_idx?;
+        if idx + 1 < self.entries.len() {
+            self.browse_idx = Some(idx + 1);
+            self.entries.get(idx + 1).cloned()
+        } else {
+            self.browse_idx = None;
+            Some(std::mem::take(&mut self.draft))
+        }
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        self.entries.get(idx).map(String::as_str)
+    }
+
+    /// Newest entry at or before `before` containing `query`.
+    pub fn search_backward(&self, query: &str, before: usize) -> Option<usize> {
+        let end = before.saturating_add(1).min(self.entries.len());
+        self.entries[..end]
+            .iter()
+            .rposition(|entry| entry.contains(query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn config(size: usize, dedup: bool) -> HistoryConfig {
+        HistoryConfig {
+            size,
+            dedup,
+            persist: true,
+        }
+    }
+
+    // a history file of its own, like the one below the state dir
+    fn history_file() -> (TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history").join("%project");
+        (dir, path)
+    }
+
+    fn entries(history: &PromptHistory) -> Vec<&str> {
+        history.entries.iter().map(String::as_str).collect()
+    }
+
+    fn file_lines(path: &Path) -> usize {
+        fs::read_to_string(path).unwrap().lines().count()
+    }
+
+    #[test]
+    fn entries_survive_the_file_escaped() {
+        let (_dir, path) = history_file();
+        let written = [
+            "two\\nlines",
+            "real\nnewline",
+            "trailing \\",
+            "back\\slash\\n",
+        ];
+        let mut history = PromptHistory::load_from(&config(10, true), Some(path.clone()));
+        for entry in written {
+            history.push(entry);
+        }
+        assert_eq!(file_lines(&path), written.len());
+
+        let reloaded = PromptHistory::load_from(&config(10, true), Some(path));
+        assert_eq!(entries(&reloaded), written);
+    }
+
+    #[test]
+    fn dedup_moves_repeats_to_the_end() {
+        let (_dir, path) = history_file();
+        let mut history = PromptHistory::load_from(&config(10, true), Some(path));
+        for entry in ["a", "b", "a", "c", "c"] {
+            history.push(entry);
+        }
+        assert_eq!(entries(&history), ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn without_dedup_only_direct_repeats_are_dropped() {
+        let (_dir, path) = history_file();
+        let mut history = PromptHistory::load_from(&config(10, false), Some(path.clone()));
+        for entry in ["a", "a", "b", "a"] {
+            history.push(entry);
+        }
+        assert_eq!(entries(&history), ["a", "b", "a"]);
+        // the dropped repeat is not written either
+        assert_eq!(file_lines(&path), 3);
+    }
+
+    #[test]
+    fn file_is_compacted_past_half_again_the_limit() {
+        let (_dir, path) = history_file();
+        let mut history = PromptHistory::load_from(&config(4, true), Some(path.clone()));
+        for entry in ["1", "2", "3", "4", "5", "6"] {
+            history.push(entry);
+        }
+        assert_eq!(file_lines(&path), 6);
+        assert_eq!(entries(&history), ["3", "4", "5", "6"]);
+
+        history.push("7");
+        assert_eq!(file_lines(&path), 4);
+        assert_eq!(entries(&history), ["4", "5", "6", "7"]);
+    }
+
+    #[test]
+    fn other_sessions_are_merged_on_reload() {
+        let (_dir, path) = history_file();
+        let mut first = PromptHistory::load_from(&config(4, true), Some(path.clone()));
+        let mut second = PromptHistory::load_from(&config(4, true), Some(path.clone()));
+        first.push("mine");
+        second.push("theirs");
+        first.push("mine again");
+        assert_eq!(entries(&first), ["mine", "mine again"]);
+
+        let reloaded = PromptHistory::load_from(&config(4, true), Some(path.clone()));
+        assert_eq!(entries(&reloaded), ["mine", "theirs", "mine again"]);
+
+        // compacting keeps what the other session wrote in the meantime
+        for entry in ["a", "b", "c"] {
+            first.push(entry);
+        }
+        second.push("late");
+        first.push("d");
+        assert_eq!(file_lines(&path), 8);
+        first.push("e");
+        assert_eq!(file_lines(&path), 4);
+        assert_eq!(entries(&first), ["c", "late", "d", "e"]);
+    }
+
+    #[test]
+    fn search_backward_finds_the_newest_match() {
+        let mut history = PromptHistory::load_from(&config(10, true), None);
+        for entry in ["cargo build", "git status", "cargo test"] {
+            history.push(entry);
+        }
+        assert_eq!(history.search_backward("cargo", 2), Some(2));
+        assert_eq!(history.search_backward("cargo", 1), Some(0));
+        assert_eq!(history.search_backward("cargo", 99), Some(2));
+        assert_eq!(history.search_backward("make", 2), None);
+    }
+}
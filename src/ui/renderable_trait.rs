@@ -1,8 +1,23 @@
+use crossterm::event::KeyEvent;
 use ratatui::{buffer::Buffer, layout::Rect};
 
-use crate::ui::app_ui_state::AppUIState;
+use crate::ui::{
+    app_ui_state::AppUIState,
+    event_system::{EventSignal, KeyOperationEvent},
+};
 
 pub trait Renderable {
     fn area_rect(&self, area: Rect) -> Rect;
     fn render(&self, area: Rect, buf: &mut Buffer, state: &mut AppUIState);
+
+    /// Handles a key operation routed to this component.
+    /// Returns `None` if the operation is not one of its own, so the next component can take it.
+    fn handle_key_operation(
+        &self,
+        _key_operation_event: KeyOperationEvent,
+        _key: KeyEvent,
+        _state: &mut AppUIState,
+    ) -> Option<EventSignal> {
+        None
+    }
 }
@@ -0,0 +1,338 @@
+use std::fs;
+
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, bail, eyre},
+};
+use tui_input::Input;
+
+use crate::{
+    back_logic::{
+        cargo::CargoCheck, git::GitContext, message_loop::Command, project_files::read_attachable,
+        tmux::DEFAULT_SCROLLBACK_LINES,
+    },
+    ui::{
+        app_ui_state::{AppUIState, ChatMessage, ChatState, CheckBoxEntry, ContextSource},
+        conversation::{SavedConversation, saved_names, timestamp},
+        event_system::EventSignal,
+        nask_center_input::clamp_input_scroll,
+        overlay::Toast,
+        tmux_panes::open_tmux_picker,
+    },
+};
+
+type SlashHandler = fn(&mut AppUIState, &str) -> Result<String>;
+
+enum SlashRun {
+    Local(SlashHandler), // gets the text after the name, returns a confirmation for the toast (empty for none)
+    Signal(EventSignal), // leaves the text after the name in the prompt for the main loop to pick up
+}
+
+/// A `/name args` prompt handled locally instead of being sent to the model.
+pub struct SlashCommand {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub help: &'static str,
+    run: SlashRun,
+}
+
+pub const SLASH_COMMANDS: &[SlashCommand] = &[
+    SlashCommand {
+        name: "model",
+        usage: "/model <name>",
+        help: "switch the model for the next prompts",
+        run: SlashRun::Local(run_model),
+    },
+    SlashCommand {
+        name: "clear",
+        usage: "/clear",
+        help: "start a new conversation",
+        run: SlashRun::Local(run_clear),
+    },
+    SlashCommand {
+        name: "save",
+        usage: "/save [name]",
+        help: "save the conversation",
+        run: SlashRun::Local(run_save),
+    },
+    SlashCommand {
+        name: "load",
+        usage: "/load [name]",
+        help: "load a saved conversation, the latest without a name",
+        run: SlashRun::Local(run_load),
+    },
+    SlashCommand {
+        name: "system",
+        usage: "/system <text>",
+        help: "set the system prompt, without text clear it",
+        run: SlashRun::Local(run_system),
+    },
+    SlashCommand {
+        name: "context",
+        usage: "/context add <path>",
+        help: "attach a file as context",
+        run: SlashRun::Local(run_context),
+    },
+    SlashCommand {
+        name: "git",
+        usage: "/git diff|staged|branch <name>|log <path> [n]",
+        help: "attach git output as context, re-run on every send",
+        run: SlashRun::Local(run_git),
+    },
+    SlashCommand {
+        name: "cargo",
+        usage: "/cargo check|clippy",
+        help: "attach compiler messages as context, re-run on every send",
+        run: SlashRun::Local(run_cargo),
+    },
+    SlashCommand {
+        name: "fix",
+        usage: "/fix",
+        help: "ask the model to fix the first cargo check error",
+        run: SlashRun::Local(run_fix),
+    },
+    SlashCommand {
+        name: "run",
+        usage: "/run <command>",
+        help: "run a shell command and attach its output, also `!command`",
+        run: SlashRun::Local(run_run),
+    },
+    SlashCommand {
+        name: "tmux",
+        usage: "/tmux [pane] [lines]",
+        help: "attach the scrollback of a tmux pane, without a pane pick one",
+        run: SlashRun::Local(run_tmux),
+    },
+    SlashCommand {
+        name: "edit",
+        usage: "/edit [text]",
+        help: "write the prompt in $EDITOR, starting from the text",
+        run: SlashRun::Signal(EventSignal::EditInExternalEditor),
+    },
+    SlashCommand {
+        name: "export",
+        usage: "/export md [path]",
+        help: "write the conversation as markdown",
+        run: SlashRun::Local(run_export),
+    },
+];
+
+fn args(rest: &str) -> Vec<&str> {
+    rest.split_whitespace().collect()
+}
+
+fn usage_error(command: &str) -> color_eyre::Report {
+    let usage = SLASH_COMMANDS
+        .iter()
+        .find(|c| c.name == command)
+        .map(|c| c.usage)
+        .unwrap_or(command);
+    eyre!("usage: {}", usage)
+}
+
+fn run_model(state: &mut AppUIState, rest: &str) -> Result<String> {
+    let [name] = args(rest)[..] else {
+        return Err(usage_error("model"));
+    };
+    state.meta_info_state.model_name = name.to_string();
+    Ok(format!("model: {}", name))
+}
+
+// the answer on its way would land in the replaced transcript
+fn refuse_in_flight(state: &AppUIState) -> Result<()> {
+    if state.prompt_in_flight() {
+        bail!("a prompt is still on its way, wait for the answer");
+    }
+    Ok(())
+}
+
+fn run_clear(state: &mut AppUIState, rest: &str) -> Result<String> {
+    if !rest.trim().is_empty() {
+        return Err(usage_error("clear"));
+    }
+    refuse_in_flight(state)?;
+    // the system prompt belongs to the session, not the transcript
+    state.chat_state = ChatState {
+        system_prompt: state.chat_state.system_prompt.take(),
+        ..ChatState::default()
+    };
+    Ok(String::from("conversation cleared"))
+}
+
+fn run_save(state: &mut AppUIState, rest: &str) -> Result<String> {
+    let name = match args(rest)[..] {
+        [] => format!("conversation-{}", timestamp()),
+        [name] => name.to_string(),
+        _ => return Err(usage_error("save")),
+    };
+    if state.chat_state.chat_messages.is_empty() {
+        bail!("nothing to save yet");
+    }
+    SavedConversation::from_chat(&state.chat_state).save(&name)?;
+    Ok(format!("saved as {}", name))
+}
+
+fn run_load(state: &mut AppUIState, rest: &str) -> Result<String> {
+    let name = match args(rest)[..] {
+        [] => saved_names()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| eyre!("no saved conversations"))?,
+        [name] => name.to_string(),
+        _ => return Err(usage_error("load")),
+    };
+    refuse_in_flight(state)?;
+    SavedConversation::load(&name)?.restore(&mut state.chat_state);
+    Ok(format!("loaded {}", name))
+}
+
+fn run_system(state: &mut AppUIState, rest: &str) -> Result<String> {
+    let text = rest.trim();
+    if text.is_empty() {
+        state.chat_state.system_prompt = None;
+        return Ok(String::from("system prompt cleared"));
+    }
+    state.chat_state.system_prompt = Some(text.to_string());
+    Ok(String::from("system prompt set"))
+}
+
+fn run_context(state: &mut AppUIState, rest: &str) -> Result<String> {
+    let ["add", path] = args(rest)[..] else {
+        return Err(usage_error("context"));
+    };
+    state.additional_context_state.attach(CheckBoxEntry {
+        checked: true,
+        selected: false,
+        entry: path.to_string(),
+        content: Some(read_attachable(path)?),
+        source: ContextSource::File,
+    });
+    Ok(format!("attached {}", path))
+}
+
+const DEFAULT_LOG_COUNT: usize = 5;
+
+fn run_git(state: &mut AppUIState, rest: &str) -> Result<String> {
+    let git = match args(rest)[..] {
+        ["diff"] => GitContext::Unstaged,
+        ["staged"] => GitContext::Staged,
+        ["branch", branch] => GitContext::Branch(branch.to_string()),
+        ["log", path] => GitContext::Log {
+            path: path.to_string(),
+            count: DEFAULT_LOG_COUNT,
+        },
+        ["log", path, count] => GitContext::Log {
+            path: path.to_string(),
+            count: count.parse().map_err(|_| usage_error("git"))?,
+        },
+        _ => return Err(usage_error("git")),
+    };
+    // the output arrives as a context entry once git is done
+    let title = git.title();
+    (state.pump_message_loop)(Command::RunGit { git, review: false });
+    Ok(format!("running {}", title))
+}
+
+fn run_cargo(state: &mut AppUIState, rest: &str) -> Result<String> {
+    let cargo = match args(rest)[..] {
+        ["check"] => CargoCheck::Check,
+        ["clippy"] => CargoCheck::Clippy,
+        _ => return Err(usage_error("cargo")),
+    };
+    // the diagnostics arrive as a context entry once the build is done
+    (state.pump_message_loop)(Command::RunCargo { cargo, fix: false });
+    Ok(format!("running {}", cargo.title()))
+}
+
+fn run_fix(state: &mut AppUIState, rest: &str) -> Result<String> {
+    if !rest.trim().is_empty() {
+        return Err(usage_error("fix"));
+    }
+    let cargo = CargoCheck::Check;
+    (state.pump_message_loop)(Command::RunCargo { cargo, fix: true });
+    Ok(format!("running {}", cargo.title()))
+}
+
+fn run_run(state: &mut AppUIState, rest: &str) -> Result<String> {
+    let command = rest.trim();
+    if command.is_empty() {
+        return Err(usage_error("run"));
+    }
+    // the output arrives as a context entry once the command is done
+    (state.pump_message_loop)(Command::RunShell {
+        command: command.to_string(),
+    });
+    Ok(format!("running `{}`", command))
+}
+
+fn run_tmux(state: &mut AppUIState, rest: &str) -> Result<String> {
+    let (pane, lines) = match args(rest)[..] {
+        [] => {
+            open_tmux_picker(state);
+            return Ok(String::new());
+        }
+        [pane] => (pane, DEFAULT_SCROLLBACK_LINES),
+        [pane, lines] => (pane, lines.parse().map_err(|_| usage_error("tmux"))?),
+        _ => return Err(usage_error("tmux")),
+    };
+    // the scrollback arrives as a context entry once tmux is done
+    (state.pump_message_loop)(Command::CaptureTmux {
+        socket: state.tmux_socket.clone(),
+        pane: pane.to_string(),
+        lines,
+    });
+    Ok(format!("capturing tmux pane {}", pane))
+}
+
+fn run_export(state: &mut AppUIState, rest: &str) -> Result<String> {
+    let path = match args(rest)[..] {
+        ["md"] => format!("nask-{}.md", timestamp()),
+        ["md", path] => path.to_string(),
+        _ => return Err(usage_error("export")),
+    };
+    let markdown = SavedConversation::from_chat(&state.chat_state).to_markdown();
+    fs::write(&path, markdown).wrap_err_with(|| format!("writing {}", path))?;
+    Ok(format!("exported to {}", path))
+}
+
+/// Runs `line` (starting with `/`), failures and unknown commands show up in the chat.
+/// Commands that need the main loop, like `/edit`, hand back their signal.
+pub fn run_slash_command(state: &mut AppUIState, line: &str) -> EventSignal {
+    let line = line.trim_start_matches('/');
+    let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+    let result = match SLASH_COMMANDS
+        .iter()
+        .find(|c| c.name == name)
+        .map(|c| &c.run)
+    {
+        Some(SlashRun::Local(run)) => run(state, rest).map_err(|e| format!("/{}: {:#}", name, e)),
+        Some(SlashRun::Signal(signal)) => {
+            let input_box_state = &mut state.input_box_state;
+            input_box_state.input = Input::new(rest.trim().to_string());
+            clamp_input_scroll(input_box_state);
+            return *signal;
+        }
+        None => {
+            let known: Vec<String> = SLASH_COMMANDS
+                .iter()
+                .map(|c| format!("/{}", c.name))
+                .collect();
+            Err(format!(
+                "unknown command `/{}`, available: {} (start with // to send a literal /)",
+                name,
+                known.join(", ")
+            ))
+        }
+    };
+
+    match result {
+        Ok(confirmation) if confirmation.is_empty() => {}
+        Ok(confirmation) => state.overlays.push(Toast::new(confirmation)),
+        Err(message) => state
+            .chat_state
+            .chat_messages
+            .push(ChatMessage::error(message)),
+    }
+    EventSignal::Continue
+}
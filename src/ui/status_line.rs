@@ -0,0 +1,138 @@
+use crate::ui::app_ui_state::{AppUIState, Focus, InputMode};
+use crate::ui::layout::is_narrow;
+use crate::ui::renderable_trait::Renderable;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+};
+
+pub struct StatusLine;
+
+pub fn create_status_line() -> &'static dyn Renderable {
+    static STATUS: StatusLine = StatusLine;
+    &STATUS
+}
+
+const SPINNER_FRAME_MS: u128 = 100;
+
+fn format_tokens(tokens: usize) -> String {
+    if tokens < 1000 {
+        tokens.to_string()
+    } else {
+        format!("{:.1}k", tokens as f64 / 1000.0)
+    }
+}
+
+// checked contexts plus what is typed so far, same estimate as the context panel
+fn estimated_prompt_tokens(state: &AppUIState) -> usize {
+    let contexts: usize = state
+        .additional_context_state
+        .entries
+        .iter()
+        .filter(|e| e.checked)
+        .filter_map(|e| e.estimated_tokens())
+        .sum();
+    contexts
+        + state
+            .input_box_state
+            .input
+            .value()
+            .chars()
+            .count()
+            .div_ceil(4)
+}
+
+impl Renderable for StatusLine {
+    fn area_rect(&self, area: Rect) -> Rect {
+        area
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, state: &mut AppUIState) {
+        let theme = &state.theme;
+        let dim = Style::default().fg(theme.dim);
+        let separator = Span::styled(format!(" {} ", theme.glyphs.separator), dim);
+
+        let mode = match state.input_box_state.mode {
+            InputMode::Insert => " INSERT ",
+            InputMode::Normal => " NORMAL ",
+        };
+        let mode_bg = match state.input_box_state.mode {
+            InputMode::Insert => theme.accent,
+            InputMode::Normal => theme.semi_accent,
+        };
+        let pane = match state.focus {
+            Focus::Input => "input",
+            Focus::Chat => "chat",
+            Focus::Contexts => "contexts",
+        };
+
+        // without colors the badge only stands out in reverse video
+        let mut badge = Style::default()
+            .fg(theme.surface)
+            .bg(mode_bg)
+            .add_modifier(Modifier::BOLD);
+        if mode_bg == Color::Reset {
+            badge = badge.add_modifier(Modifier::REVERSED);
+        }
+        let mut left = vec![
+            Span::styled(mode, badge),
+            Span::raw(" "),
+            Span::styled(pane, dim),
+        ];
+
+        let generation = &state.generation;
+        if let Some(started) = generation.started {
+            let spinner = theme.glyphs.spinner;
+            let frame = (started.elapsed().as_millis() / SPINNER_FRAME_MS) as usize % spinner.len();
+            left.push(separator.clone());
+            left.push(Span::styled(
+                spinner[frame],
+                Style::default().fg(theme.accent),
+            ));
+            left.push(Span::raw(" generating"));
+        }
+        if let Some(elapsed) = generation.elapsed() {
+            left.push(separator.clone());
+            left.push(Span::styled(format!("{:.1}s", elapsed.as_secs_f64()), dim));
+            if let Some(rate) = generation.tokens_per_second() {
+                left.push(Span::styled(format!(" {:.0} tok/s", rate), dim));
+            }
+        }
+
+        let right = Line::from(vec![
+            Span::styled(
+                format!(
+                    "prompt ~{} tok",
+                    format_tokens(estimated_prompt_tokens(state))
+                ),
+                dim,
+            ),
+            separator,
+            Span::styled(
+                format!(
+                    "session {} tok",
+                    format_tokens(generation.session_usage.total())
+                ),
+                dim,
+            ),
+            Span::raw(" "),
+        ]);
+
+        let left = Line::from(left);
+        // counters only when they fit next to the mode and timing
+        let show_right = !is_narrow(area) && left.width() + right.width() < area.width as usize;
+        Paragraph::new(left)
+            .style(Style::default().bg(theme.surface))
+            .render(area, buf);
+        if show_right {
+            Paragraph::new(right)
+                .alignment(Alignment::Right)
+                .render(area, buf);
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use std::env;
+
+use ratatui::style::Color;
+
+use crate::config::app_config::ColorMode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSupport {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    NoColor,
+}
+
+impl ColorSupport {
+    pub fn from_config(mode: ColorMode) -> Self {
+        match mode {
+            ColorMode::Auto => Self::detect(),
+            ColorMode::Truecolor => ColorSupport::TrueColor,
+            ColorMode::Ansi256 => ColorSupport::Ansi256,
+            ColorMode::Ansi16 => ColorSupport::Ansi16,
+            ColorMode::None => ColorSupport::NoColor,
+        }
+    }
+
+    // https://no-color.org, then the usual COLORTERM/TERM conventions;
+    // tmux and screen only pass truecolor through when COLORTERM says so
+    fn detect() -> Self {
+        let var = |name| env::var(name).unwrap_or_default();
+        Self::detect_from(&var("NO_COLOR"), &var("COLORTERM"), &var("TERM"))
+    }
+
+    fn detect_from(no_color: &str, colorterm: &str, term: &str) -> Self {
+        if !no_color.is_empty() {
+            return ColorSupport::NoColor;
+        }
+
+        let colorterm = colorterm.to_ascii_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorSupport::TrueColor;
+        }
+
+        if term == "dumb" {
+            ColorSupport::NoColor
+        } else if term.ends_with("-direct") {
+            ColorSupport::TrueColor
+        } else if term.contains("256color") {
+            ColorSupport::Ansi256
+        } else {
+            ColorSupport::Ansi16
+        }
+    }
+
+    /// Nearest color the terminal can show.
+    pub fn downsample(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorSupport::TrueColor, c) => c,
+            (ColorSupport::NoColor, _) => Color::Reset,
+            (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_256(r, g, b)),
+            (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => nearest_16(r, g, b),
+            (ColorSupport::Ansi16, Color::Indexed(idx)) if idx >= 16 => {
+                let (r, g, b) = indexed_to_rgb(idx);
+                nearest_16(r, g, b)
+            }
+            (_, c) => c,
+        }
+    }
+}
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+// the 16 base colors as xterm draws them
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+fn indexed_to_rgb(idx: u8) -> (u8, u8, u8) {
+    match idx {
+        0..=15 => ANSI_16[idx as usize].1,
+        16..=231 => {
+            let i = idx - 16;
+            (
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[(i / 6 % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + (idx - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+// best of the 6x6x6 cube and the 24 step gray ramp
+fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    let cube_index = |v: u8| {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, level)| (v as i32 - **level as i32).abs())
+            .map(|(i, _)| i as u8)
+            .unwrap_or(0)
+    };
+    let cube = 16 + 36 * cube_index(r) + 6 * cube_index(g) + cube_index(b);
+
+    let avg = (r as u32 + g as u32 + b as u32) / 3;
+    let gray = 232 + ((avg.saturating_sub(3)) / 10).min(23) as u8;
+
+    let target = (r, g, b);
+    if distance(indexed_to_rgb(gray), target) < distance(indexed_to_rgb(cube), target) {
+        gray
+    } else {
+        cube
+    }
+}
+
+fn nearest_16(r: u8, g: u8, b: u8) -> Color {
+    ANSI_16
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb, (r, g, b)))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Modifier;
+
+    use crate::{config::app_config::DisplayConfig, ui::theme::Theme};
+
+    use super::*;
+
+    #[test]
+    fn detect_follows_the_environment() {
+        for (no_color, colorterm, term, expected) in [
+            ("", "", "xterm-256color", ColorSupport::Ansi256),
+            ("", "truecolor", "xterm-256color", ColorSupport::TrueColor),
+            ("", "24BIT", "screen", ColorSupport::TrueColor),
+            ("", "", "xterm-direct", ColorSupport::TrueColor),
+            ("", "", "xterm", ColorSupport::Ansi16),
+            ("", "", "dumb", ColorSupport::NoColor),
+            ("1", "truecolor", "xterm-256color", ColorSupport::NoColor),
+        ] {
+            assert_eq!(
+                ColorSupport::detect_from(no_color, colorterm, term),
+                expected,
+                "NO_COLOR={:?} COLORTERM={:?} TERM={:?}",
+                no_color,
+                colorterm,
+                term
+            );
+        }
+    }
+
+    #[test]
+    fn nearest_256_picks_the_cube_or_the_gray_ramp() {
+        for (rgb, expected) in [
+            ((0, 0, 0), 16),
+            ((255, 255, 255), 231),
+            ((255, 0, 0), 196),
+            ((95, 135, 175), 67),
+            // pure grays between the cube levels land on the ramp
+            ((128, 128, 128), 244),
+            ((8, 8, 8), 232),
+            ((40, 44, 52), 236),
+        ] {
+            assert_eq!(nearest_256(rgb.0, rgb.1, rgb.2), expected, "{:?}", rgb);
+        }
+    }
+
+    #[test]
+    fn nearest_16_picks_the_closest_base_color() {
+        for (rgb, expected) in [
+            ((0, 0, 0), Color::Black),
+            ((200, 10, 10), Color::Red),
+            ((250, 20, 20), Color::LightRed),
+            ((120, 130, 125), Color::DarkGray),
+            ((100, 160, 220), Color::LightBlue),
+            ((80, 80, 250), Color::LightBlue),
+        ] {
+            assert_eq!(nearest_16(rgb.0, rgb.1, rgb.2), expected, "{:?}", rgb);
+        }
+    }
+
+    #[test]
+    fn downsample_maps_into_the_supported_range() {
+        let rgb = Color::Rgb(255, 0, 0);
+        assert_eq!(ColorSupport::TrueColor.downsample(rgb), rgb);
+        assert_eq!(ColorSupport::Ansi256.downsample(rgb), Color::Indexed(196));
+        assert_eq!(ColorSupport::Ansi16.downsample(rgb), Color::LightRed);
+        assert_eq!(
+            ColorSupport::Ansi16.downsample(Color::Indexed(232)),
+            Color::Black
+        );
+        assert_eq!(ColorSupport::NoColor.downsample(rgb), Color::Reset);
+    }
+
+    #[test]
+    fn no_color_selection_is_reversed() {
+        let display = DisplayConfig {
+            colors: ColorMode::None,
+            ascii: false,
+        };
+        let theme = Theme::DARK.for_terminal(&display);
+        assert!(
+            theme
+                .selection_style()
+                .add_modifier
+                .contains(Modifier::REVERSED)
+        );
+        assert!(
+            !Theme::DARK
+                .selection_style()
+                .add_modifier
+                .contains(Modifier::REVERSED)
+        );
+    }
+}
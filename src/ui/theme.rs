@@ -0,0 +1,218 @@
+use std::{fs, str::FromStr};
+
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, bail, eyre},
+};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    symbols::border,
+};
+
+use crate::{
+    config::app_config::{DisplayConfig, ThemeConfig, config_dir},
+    ui::terminal_caps::ColorSupport,
+};
+
+/// Non-color symbols, swapped for plain ASCII on limited fonts.
+#[derive(PartialEq)]
+pub struct Glyphs {
+    pub banner: &'static str,
+    pub border: border::Set<'static>,
+    pub collapsed: &'static str,
+    pub ellipsis: &'static str,
+    pub focus_marker: &'static str,
+    pub newline: char,
+    pub separator: &'static str,
+    pub spinner: &'static [&'static str],
+}
+
+impl Glyphs {
+    pub const UNICODE: Glyphs = Glyphs {
+        banner: include_str!("../../assets/nask.txt"),
+        border: border::PLAIN,
+        collapsed: "▴",
+        ellipsis: "…",
+        focus_marker: "▎",
+        newline: '↵',
+        separator: "·",
+        spinner: &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
+    };
+
+    pub const ASCII: Glyphs = Glyphs {
+        banner: include_str!("../../assets/nask_ascii.txt"),
+        border: border::Set {
+            top_left: "+",
+            top_right: "+",
+            bottom_left: "+",
+            bottom_right: "+",
+            vertical_left: "|",
+            vertical_right: "|",
+            horizontal_top: "-",
+            horizontal_bottom: "-",
+        },
+        collapsed: "^",
+        ellipsis: "...",
+        focus_marker: "|",
+        newline: '~',
+        separator: "-",
+        spinner: &["|", "/", "-", "\\"],
+    };
+}
+
+const ROLES: [&str; 10] = [
+    "accent",
+    "semi_accent",
+    "border",
+    "user_message",
+    "assistant_message",
+    "dim",
+    "error",
+    "code_background",
+    "selection",
+    "surface",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub accent: Color,
+    pub semi_accent: Color, // focused input in normal mode
+    pub border: Color,
+    pub user_message: Color,
+    pub assistant_message: Color,
+    pub dim: Color,
+    pub error: Color,
+    pub code_background: Color,
+    pub selection: Color,
+    pub surface: Color, // background of bars and popups
+    pub glyphs: &'static Glyphs,
+}
+
+impl Theme {
+    pub const DARK: Theme = Theme {
+        accent: Color::Rgb(100, 160, 220),
+        semi_accent: Color::Rgb(90, 120, 150),
+        border: Color::Reset,
+        user_message: Color::Rgb(100, 160, 220),
+        assistant_message: Color::Reset,
+        dim: Color::DarkGray,
+        error: Color::Red,
+        code_background: Color::Rgb(30, 30, 30),
+        selection: Color::Rgb(40, 44, 52),
+        surface: Color::Rgb(22, 22, 22),
+        glyphs: &Glyphs::UNICODE,
+    };
+
+    pub const LIGHT: Theme = Theme {
+        accent: Color::Rgb(30, 100, 180),
+        semi_accent: Color::Rgb(100, 130, 170),
+        border: Color::Reset,
+        user_message: Color::Rgb(30, 100, 180),
+        assistant_message: Color::Reset,
+        dim: Color::Gray,
+        error: Color::Rgb(190, 30, 30),
+        code_background: Color::Rgb(238, 238, 238),
+        selection: Color::Rgb(215, 225, 240),
+        surface: Color::Rgb(245, 245, 245),
+        glyphs: &Glyphs::UNICODE,
+    };
+
+    /// The selected row, in reverse video when the selection has no color of its own,
+    /// e.g. under `NO_COLOR`.
+    pub fn selection_style(&self) -> Style {
+        if self.selection == Color::Reset {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().bg(self.selection)
+        }
+    }
+
+    fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Self::DARK),
+            "light" => Some(Self::LIGHT),
+            _ => None,
+        }
+    }
+
+    /// Adapts the theme to what the terminal (and its font) can show.
+    pub fn for_terminal(mut self, display: &DisplayConfig) -> Theme {
+        let support = ColorSupport::from_config(display.colors);
+        for role in ROLES {
+            if let Some(color) = self.role_mut(role) {
+                *color = support.downsample(*color);
+            }
+        }
+        if display.ascii {
+            self.glyphs = &Glyphs::ASCII;
+        }
+        self
+    }
+
+    fn role_mut(&mut self, role: &str) -> Option<&mut Color> {
+        Some(match role {
+            "accent" => &mut self.accent,
+            "semi_accent" => &mut self.semi_accent,
+            "border" => &mut self.border,
+            "user_message" => &mut self.user_message,
+            "assistant_message" => &mut self.assistant_message,
+            "dim" => &mut self.dim,
+            "error" => &mut self.error,
+            "code_background" => &mut self.code_background,
+            "selection" => &mut self.selection,
+            "surface" => &mut self.surface,
+            _ => return None,
+        })
+    }
+
+    fn apply_colors<'a>(
+        &mut self,
+        colors: impl IntoIterator<Item = (&'a String, &'a String)>,
+    ) -> Result<()> {
+        for (role, value) in colors {
+            let color = Color::from_str(value)
+                .map_err(|_| eyre!("theme color `{}`: invalid color `{}`", role, value))?;
+            *self
+                .role_mut(role)
+                .ok_or_else(|| eyre!("unknown theme color `{}`", role))? = color;
+        }
+        Ok(())
+    }
+
+    /// Built-in theme or `<config dir>/themes/<name>.toml`, with the `[theme.colors]` overrides on top.
+    pub fn from_config(config: &ThemeConfig) -> Result<Theme> {
+        let mut theme = Self::named(&config.name, 0)?;
+        theme.apply_colors(&config.colors)?;
+        Ok(theme)
+    }
+
+    // theme files may build on another theme through their own `name`
+    fn named(name: &str, depth: usize) -> Result<Theme> {
+        if let Some(theme) = Self::builtin(name) {
+            return Ok(theme);
+        }
+        if depth > 8 {
+            bail!("theme `{}`: too many nested base themes", name);
+        }
+
+        let path = config_dir()
+            .map(|d| d.join("themes").join(format!("{}.toml", name)))
+            .ok_or_else(|| eyre!("unknown theme `{}`", name))?;
+        let raw = fs::read_to_string(&path)
+            .wrap_err_with(|| format!("reading theme {}", path.display()))?;
+        let file: ThemeConfig =
+            toml::from_str(&raw).wrap_err_with(|| format!("parsing theme {}", path.display()))?;
+
+        let mut theme = Self::named(&file.name, depth + 1)?;
+        theme
+            .apply_colors(&file.colors)
+            .wrap_err_with(|| format!("in theme {}", path.display()))?;
+        Ok(theme)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DARK
+    }
+}
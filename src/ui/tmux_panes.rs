@@ -0,0 +1,167 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use crate::{
+    back_logic::{
+        message_loop::Command,
+        tmux::{DEFAULT_SCROLLBACK_LINES, TmuxCapture, TmuxPane},
+    },
+    ui::{
+        app_ui_state::{AppUIState, ChatMessage, CheckBoxEntry, ContextSource},
+        list_view::{scroll_into_view, wrap_step},
+        overlay::{Overlay, OverlayOutcome, Toast, centered_rect},
+        theme::Theme,
+    },
+};
+
+const PICKER_WIDTH: u16 = 72;
+
+impl TmuxCapture {
+    /// A checked context entry holding the captured `content` of `pane`.
+    pub fn entry(self, pane: &TmuxPane, content: String) -> CheckBoxEntry {
+        CheckBoxEntry {
+            checked: true,
+            selected: false,
+            entry: format!("tmux {} {}", pane.position, pane.command),
+            content: Some(content),
+            source: ContextSource::Tmux(self),
+        }
+    }
+}
+
+/// Asks for the panes, the picker opens once tmux answered.
+pub fn open_tmux_picker(state: &mut AppUIState) {
+    (state.pump_message_loop)(Command::ListTmuxPanes {
+        socket: state.tmux_socket.clone(),
+    });
+}
+
+/// Opens the picker, or tells why there is nothing to pick from.
+pub fn tmux_panes_listed(
+    state: &mut AppUIState,
+    socket: Option<String>,
+    panes: Result<Vec<TmuxPane>, String>,
+) {
+    match panes {
+        Ok(panes) if panes.is_empty() => state
+            .overlays
+            .push(Toast::new("no other tmux panes in this session")),
+        Ok(panes) => state.overlays.push(TmuxPanePicker::new(socket, panes)),
+        Err(e) => state.overlays.push(Toast::new(e)),
+    }
+}
+
+/// Attaches the scrollback captured for `/tmux` or the picker.
+pub fn tmux_captured(
+    state: &mut AppUIState,
+    output: Result<(TmuxPane, TmuxCapture, String), String>,
+) {
+    match output {
+        Ok((pane, capture, content)) => {
+            let entry = capture.entry(&pane, content);
+            state
+                .overlays
+                .push(Toast::new(format!("attached {}", entry.entry)));
+            state.additional_context_state.attach(entry);
+        }
+        Err(e) => state
+            .chat_state
+            .chat_messages
+            .push(ChatMessage::error(format!("/tmux: {}", e))),
+    }
+}
+
+/// Lists the panes of the session, Enter attaches the highlighted one.
+pub struct TmuxPanePicker {
+    socket: Option<String>,
+    panes: Vec<TmuxPane>,
+    selected: usize,
+    scroll: usize, // a session can have more panes than the terminal has rows
+}
+
+impl TmuxPanePicker {
+    pub fn new(socket: Option<String>, panes: Vec<TmuxPane>) -> Self {
+        Self {
+            socket,
+            panes,
+            selected: 0,
+            scroll: 0,
+        }
+    }
+
+    fn step(&mut self, backward: bool) {
+        self.selected = wrap_step(self.selected, self.panes.len(), backward);
+    }
+}
+
+impl Overlay for TmuxPanePicker {
+    fn area_rect(&self, frame: Rect) -> Rect {
+        // borders and one row per pane
+        centered_rect(frame, PICKER_WIDTH, self.panes.len() as u16 + 2)
+    }
+
+    fn render(&mut self, area: Rect, buf: &mut Buffer, theme: &Theme) {
+        let block = Block::default()
+            .title(" tmux panes ")
+            .borders(Borders::ALL)
+            .border_set(theme.glyphs.border)
+            .border_style(Style::default().fg(theme.accent))
+            .style(Style::default().bg(theme.surface));
+        let height = block.inner(area).height as usize;
+        self.scroll = scroll_into_view(self.scroll, self.selected, height);
+        let lines: Vec<Line> = self
+            .panes
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(height)
+            .map(|(idx, pane)| {
+                let style = if idx == self.selected {
+                    theme.selection_style()
+                } else {
+                    Style::default()
+                };
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:<6}", pane.position),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(format!("{:<12} ", pane.command)),
+                    Span::styled(pane.title.as_str(), Style::default().fg(theme.dim)),
+                ])
+                .style(style)
+            })
+            .collect();
+        Paragraph::new(lines).block(block).render(area, buf);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> OverlayOutcome {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => {
+                let Some(pane) = self.panes.get(self.selected) else {
+                    return OverlayOutcome::Close;
+                };
+                // failures, e.g. the pane closed meanwhile, show up in the chat
+                return OverlayOutcome::CloseWithCommand(Command::CaptureTmux {
+                    socket: self.socket.clone(),
+                    pane: pane.id.clone(),
+                    lines: DEFAULT_SCROLLBACK_LINES,
+                });
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.step(true),
+            KeyCode::Down | KeyCode::Char('j') => self.step(false),
+            KeyCode::Char('p') if ctrl => self.step(true),
+            KeyCode::Char('n') if ctrl => self.step(false),
+            KeyCode::Char('q') => return OverlayOutcome::Close,
+            _ => {}
+        }
+        OverlayOutcome::Consumed
+    }
+}
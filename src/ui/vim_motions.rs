@@ -0,0 +1,525 @@
+use crate::ui::app_ui_state::NaskInputBoxState;
+
+// `99999999999p` would otherwise try to allocate the register that many times
+const MAX_COUNT: usize = 10_000;
+// a counted put of a long register stops at this many chars
+const MAX_PUT_CHARS: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VimMotion {
+    Left,
+    Right,
+    WordForward,
+    WordBackward,
+    WordEnd,
+    LineStart,
+    LineEnd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VimOperator {
+    Delete,
+    Change,
+    Yank,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VimTextObject {
+    InnerWord,
+}
+
+#[derive(PartialEq, Eq)]
+pub enum VimOutcome {
+    Stay,
+    EnterInsert,
+}
+
+#[derive(Default)]
+pub struct VimState {
+    pub count: Option<usize>,
+    pub pending_operator: Option<(VimOperator, usize)>,
+    pub register: String,
+}
+
+impl VimState {
+    pub fn is_pending(&self) -> bool {
+        self.pending_operator.is_some()
+    }
+
+    pub fn push_count(&mut self, digit: u8) {
+        let current = self.count.unwrap_or(0);
+        self.count = Some(
+            current
+                .saturating_mul(10)
+                .saturating_add(digit as usize)
+                .min(MAX_COUNT),
+        );
+    }
+
+    pub fn reset(&mut self) {
+        self.count = None;
+        self.pending_operator = None;
+    }
+
+    fn take_count(&mut self) -> usize {
+        self.count.take().unwrap_or(1).max(1)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum CharClass {
+    Blank,
+    Punct,
+    Word,
+}
+
+fn char_class(c: char) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Blank
+    } else if c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punct
+    }
+}
+
+fn next_word_start(chars: &[char], pos: usize) -> usize {
+    let len = chars.len();
+    let mut i = pos;
+    if i >= len {
+        return len;
+    }
+
+    let class = char_class(chars[i]);
+    if class != CharClass::Blank {
+        while i < len && char_class(chars[i]) == class {
+            i += 1;
+        }
+    }
+    while i < len && char_class(chars[i]) == CharClass::Blank {
+        i += 1;
+    }
+    i
+}
+
+fn prev_word_start(chars: &[char], pos: usize) -> usize {
+    let mut i = pos.min(chars.len());
+    while i > 0 && char_class(chars[i - 1]) == CharClass::Blank {
+        i -= 1;
+    }
+    if i == 0 {
+        return 0;
+    }
+
+    let class = char_class(chars[i - 1]);
+    while i > 0 && char_class(chars[i - 1]) == class {
+        i -= 1;
+    }
+    i
+}
+
+fn word_end(chars: &[char], pos: usize) -> usize {
+    let len = chars.len();
+    if len == 0 {
+        return 0;
+    }
+
+    let mut i = pos + 1;
+    while i < len && char_class(chars[i]) == CharClass::Blank {
+        i += 1;
+    }
+    if i >= len {
+        return len - 1;
+    }
+
+    let class = char_class(chars[i]);
+    while i + 1 < len && char_class(chars[i + 1]) == class {
+        i += 1;
+    }
+    i
+}
+
+// last char of the word under the cursor, the cursor itself if it already is
+fn current_word_end(chars: &[char], pos: usize) -> usize {
+    let Some(class) = chars.get(pos).map(|c| char_class(*c)) else {
+        return pos;
+    };
+    let mut i = pos;
+    while i + 1 < chars.len() && char_class(chars[i + 1]) == class {
+        i += 1;
+    }
+    i
+}
+
+fn motion_target(chars: &[char], cursor: usize, motion: VimMotion, count: usize) -> usize {
+    let len = chars.len();
+    match motion {
+        VimMotion::Left => cursor.saturating_sub(count),
+        VimMotion::Right => cursor.saturating_add(count).min(len),
+        VimMotion::LineStart => 0,
+        VimMotion::LineEnd => len.saturating_sub(1),
+        VimMotion::WordForward => (0..count).fold(cursor, |pos, _| next_word_start(chars, pos)),
+        VimMotion::WordBackward => (0..count).fold(cursor, |pos, _| prev_word_start(chars, pos)),
+        VimMotion::WordEnd => (0..count).fold(cursor, |pos, _| word_end(chars, pos)),
+    }
+}
+
+fn is_inclusive(motion: VimMotion) -> bool {
+    matches!(motion, VimMotion::WordEnd | VimMotion::LineEnd)
+}
+
+fn inner_word_range(chars: &[char], cursor: usize) -> (usize, usize) {
+    let len = chars.len();
+    if len == 0 {
+        return (0, 0);
+    }
+
+    let pos = cursor.min(len - 1);
+    let class = char_class(chars[pos]);
+
+    let mut start = pos;
+    while start > 0 && char_class(chars[start - 1]) == class {
+        start -= 1;
+    }
+    let mut end = pos + 1;
+    while end < len && char_class(chars[end]) == class {
+        end += 1;
+    }
+    (start, end)
+}
+
+// normal mode keeps the cursor on a character, never behind the last one
+fn clamp_normal_cursor(state: &mut NaskInputBoxState) {
+    let len = state.input.value().chars().count();
+    let cursor = state.input.cursor().min(len.saturating_sub(1));
+    set_value_and_cursor(state, None, cursor);
+}
+
+fn set_value_and_cursor(state: &mut NaskInputBoxState, value: Option<String>, cursor: usize) {
+    let mut input = std::mem::take(&mut state.input);
+    if let Some(v) = value {
+        input = input.with_value(v);
+    }
+    state.input = input.with_cursor(cursor);
+}
+
+fn apply_operator(
+    state: &mut NaskInputBoxState,
+    op: VimOperator,
+    start: usize,
+    end: usize,
+) -> VimOutcome {
+    let chars: Vec<char> = state.input.value().chars().collect();
+    let end = end.min(chars.len());
+    let start = start.min(end);
+
+    state.vim.register = chars[start..end].iter().collect();
+
+    match op {
+        VimOperator::Yank => {
+            set_value_and_cursor(state, None, start);
+            clamp_normal_cursor(state);
+            VimOutcome::Stay
+        }
+        VimOperator::Delete | VimOperator::Change => {
+            let remaining: String = chars[..start].iter().chain(chars[end..].iter()).collect();
+            set_value_and_cursor(state, Some(remaining), start);
+            if op == VimOperator::Change {
+                VimOutcome::EnterInsert
+            } else {
+                clamp_normal_cursor(state);
+                VimOutcome::Stay
+            }
+        }
+    }
+}
+
+pub fn handle_motion(state: &mut NaskInputBoxState, motion: VimMotion) -> VimOutcome {
+    let chars: Vec<char> = state.input.value().chars().collect();
+    let cursor = state.input.cursor();
+    let count = state.vim.take_count();
+
+    let Some((op, op_count)) = state.vim.pending_operator.take() else {
+        let target = motion_target(&chars, cursor, motion, count);
+        set_value_and_cursor(state, None, target);
+        clamp_normal_cursor(state);
+        return VimOutcome::Stay;
+    };
+    let count = count.saturating_mul(op_count);
+
+    // `cw` on a word changes to its end, like `ce` but without skipping to the next word
+    // when the cursor is on the last char already, just as in vim
+    let (motion, target) = if op == VimOperator::Change
+        && motion == VimMotion::WordForward
+        && chars
+            .get(cursor)
+            .is_some_and(|c| char_class(*c) != CharClass::Blank)
+    {
+        let first = current_word_end(&chars, cursor);
+        let target = (1..count).fold(first, |pos, _| word_end(&chars, pos));
+        (VimMotion::WordEnd, target)
+    } else {
+        (motion, motion_target(&chars, cursor, motion, count))
+    };
+
+    let (start, end) = if target >= cursor {
+        (cursor, target + usize::from(is_inclusive(motion)))
+    } else {
+        (target, cursor)
+    };
+
+    apply_operator(state, op, start, end)
+}
+
+pub fn handle_operator(state: &mut NaskInputBoxState, op: VimOperator) -> VimOutcome {
+    let count = state.vim.take_count();
+
+    match state.vim.pending_operator.take() {
+        // doubled operator (`dd`, `cc`, `yy`) works on the whole line
+        Some((pending, _)) if pending == op => {
+            let len = state.input.value().chars().count();
+            apply_operator(state, op, 0, len)
+        }
+        Some(_) => {
+            state.vim.reset();
+            VimOutcome::Stay
+        }
+        None => {
+            state.vim.pending_operator = Some((op, count));
+            VimOutcome::Stay
+        }
+    }
+}
+
+pub fn handle_text_object(state: &mut NaskInputBoxState, object: VimTextObject) -> VimOutcome {
+    let pending = state.vim.pending_operator.take();
+    state.vim.reset();
+
+    let Some((op, _)) = pending else {
+        return VimOutcome::Stay;
+    };
+
+    let chars: Vec<char> = state.input.value().chars().collect();
+    let (start, end) = match object {
+        VimTextObject::InnerWord => inner_word_range(&chars, state.input.cursor()),
+    };
+    apply_operator(state, op, start, end)
+}
+
+pub fn delete_char(state: &mut NaskInputBoxState) {
+    let count = state.vim.take_count();
+    let cursor = state.input.cursor();
+    let len = state.input.value().chars().count();
+    if cursor >= len {
+        return;
+    }
+    apply_operator(state, VimOperator::Delete, cursor, cursor + count);
+}
+
+pub fn put(state: &mut NaskInputBoxState, before: bool) {
+    let count = state.vim.take_count();
+    if state.vim.register.is_empty() {
+        return;
+    }
+
+    let register_len = state.vim.register.chars().count();
+    let count = count.min((MAX_PUT_CHARS / register_len).max(1));
+    let pasted = state.vim.register.repeat(count);
+    put_text(state, &pasted, before);
+}
+
+/// Puts `text` after the cursor, or before it, the cursor ends on its last char.
+pub fn put_text(state: &mut NaskInputBoxState, text: &str, before: bool) {
+    let chars: Vec<char> = state.input.value().chars().collect();
+    let at = if before || chars.is_empty() {
+        state.input.cursor().min(chars.len())
+    } else {
+        (state.input.cursor() + 1).min(chars.len())
+    };
+
+    let mut value: String = chars[..at].iter().collect();
+    value.push_str(text);
+    value.extend(chars[at..].iter());
+
+    let text_len = text.chars().count();
+    set_value_and_cursor(state, Some(value), at + text_len.saturating_sub(1));
+}
+
+pub fn enter_normal_mode(state: &mut NaskInputBoxState) {
+    state.vim.reset();
+    let cursor = state.input.cursor().saturating_sub(1);
+    set_value_and_cursor(state, None, cursor);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui_input::Input;
+
+    fn input(value: &str, cursor: usize) -> NaskInputBoxState {
+        NaskInputBoxState {
+            input: Input::new(value.to_string()).with_cursor(cursor),
+            ..NaskInputBoxState::default()
+        }
+    }
+
+    fn counted(state: &mut NaskInputBoxState, count: &str) {
+        for digit in count.bytes() {
+            state.vim.push_count(digit - b'0');
+        }
+    }
+
+    #[test]
+    fn word_motions() {
+        let mut state = input("foo bar.baz qux", 0);
+        handle_motion(&mut state, VimMotion::WordForward);
+        assert_eq!(state.input.cursor(), 4);
+        handle_motion(&mut state, VimMotion::WordForward);
+        assert_eq!(state.input.cursor(), 7); // `.` is a word of its own
+        handle_motion(&mut state, VimMotion::WordEnd);
+        assert_eq!(state.input.cursor(), 10);
+        handle_motion(&mut state, VimMotion::WordBackward);
+        assert_eq!(state.input.cursor(), 8);
+        handle_motion(&mut state, VimMotion::WordBackward);
+        assert_eq!(state.input.cursor(), 7);
+    }
+
+    #[test]
+    fn line_motions() {
+        let mut state = input("hello world", 5);
+        handle_motion(&mut state, VimMotion::LineEnd);
+        assert_eq!(state.input.cursor(), 10);
+        handle_motion(&mut state, VimMotion::LineStart);
+        assert_eq!(state.input.cursor(), 0);
+    }
+
+    #[test]
+    fn counted_motion() {
+        let mut state = input("one two three four", 0);
+        counted(&mut state, "3");
+        handle_motion(&mut state, VimMotion::WordForward);
+        assert_eq!(state.input.cursor(), 14);
+        assert_eq!(state.vim.count, None);
+    }
+
+    #[test]
+    fn count_is_clamped() {
+        let mut state = input("", 0);
+        counted(&mut state, "99999999999");
+        assert_eq!(state.vim.count, Some(MAX_COUNT));
+    }
+
+    #[test]
+    fn delete_word() {
+        let mut state = input("foo bar baz", 4);
+        handle_operator(&mut state, VimOperator::Delete);
+        handle_motion(&mut state, VimMotion::WordForward);
+        assert_eq!(state.input.value(), "foo baz");
+        assert_eq!(state.vim.register, "bar ");
+        assert_eq!(state.input.cursor(), 4);
+    }
+
+    #[test]
+    fn counted_delete_word() {
+        let mut state = input("a b c d", 0);
+        counted(&mut state, "2");
+        handle_operator(&mut state, VimOperator::Delete);
+        handle_motion(&mut state, VimMotion::WordForward);
+        assert_eq!(state.input.value(), "c d");
+    }
+
+    #[test]
+    fn change_word_keeps_the_blank() {
+        let mut state = input("foo bar", 0);
+        handle_operator(&mut state, VimOperator::Change);
+        let outcome = handle_motion(&mut state, VimMotion::WordForward);
+        assert!(outcome == VimOutcome::EnterInsert);
+        assert_eq!(state.input.value(), " bar");
+        assert_eq!(state.input.cursor(), 0);
+    }
+
+    #[test]
+    fn change_word_on_its_last_char() {
+        let mut state = input("foo bar", 2);
+        handle_operator(&mut state, VimOperator::Change);
+        handle_motion(&mut state, VimMotion::WordForward);
+        assert_eq!(state.input.value(), "fo bar");
+        assert_eq!(state.vim.register, "o");
+    }
+
+    #[test]
+    fn yank_line_and_put() {
+        let mut state = input("ab", 0);
+        handle_operator(&mut state, VimOperator::Yank);
+        handle_operator(&mut state, VimOperator::Yank);
+        assert_eq!(state.vim.register, "ab");
+        assert_eq!(state.input.value(), "ab");
+
+        put(&mut state, false);
+        assert_eq!(state.input.value(), "aabb");
+        assert_eq!(state.input.cursor(), 2);
+
+        put(&mut state, true);
+        assert_eq!(state.input.value(), "aaabbb");
+    }
+
+    #[test]
+    fn counted_put() {
+        let mut state = input("x", 0);
+        state.vim.register = String::from("ab");
+        counted(&mut state, "3");
+        put(&mut state, false);
+        assert_eq!(state.input.value(), "xababab");
+    }
+
+    #[test]
+    fn counted_put_is_capped() {
+        let mut state = input("", 0);
+        state.vim.register = "x".repeat(1000);
+        counted(&mut state, "9999");
+        put(&mut state, false);
+        assert_eq!(state.input.value().len(), MAX_PUT_CHARS / 1000 * 1000);
+        assert_eq!(state.input.cursor(), MAX_PUT_CHARS / 1000 * 1000 - 1);
+    }
+
+    #[test]
+    fn delete_chars() {
+        let mut state = input("abcd", 1);
+        delete_char(&mut state);
+        assert_eq!(state.input.value(), "acd");
+        assert_eq!(state.vim.register, "b");
+
+        counted(&mut state, "5");
+        delete_char(&mut state);
+        assert_eq!(state.input.value(), "a");
+        assert_eq!(state.input.cursor(), 0);
+    }
+
+    #[test]
+    fn delete_inner_word() {
+        let mut state = input("foo bar baz", 5);
+        handle_operator(&mut state, VimOperator::Delete);
+        let outcome = handle_text_object(&mut state, VimTextObject::InnerWord);
+        assert!(outcome == VimOutcome::Stay);
+        assert_eq!(state.input.value(), "foo  baz");
+        assert_eq!(state.vim.register, "bar");
+        assert_eq!(state.input.cursor(), 4);
+    }
+
+    #[test]
+    fn change_inner_word() {
+        let mut state = input("foo.bar", 1);
+        handle_operator(&mut state, VimOperator::Change);
+        let outcome = handle_text_object(&mut state, VimTextObject::InnerWord);
+        assert!(outcome == VimOutcome::EnterInsert);
+        assert_eq!(state.input.value(), ".bar");
+        assert_eq!(state.input.cursor(), 0);
+    }
+
+    #[test]
+    fn text_object_without_operator() {
+        let mut state = input("foo bar", 1);
+        handle_text_object(&mut state, VimTextObject::InnerWord);
+        assert_eq!(state.input.value(), "foo bar");
+    }
+}